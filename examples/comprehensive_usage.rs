@@ -28,16 +28,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 async fn basic_usage() -> Result<(), Box<dyn std::error::Error>> {
     let mut stream = watch_log("fixtures/simple_append.log", None).await?;
 
-    let mut batch_count = 0;
-    while let Some(lines_result) = stream.next().await {
+    // Just show the first batch for demo
+    if let Some(lines_result) = stream.next().await {
         match lines_result {
             Ok(lines) => {
-                batch_count += 1;
-                println!(
-                    "  📦 Batch #{} received {} lines:",
-                    batch_count,
-                    lines.len()
-                );
+                println!("  📦 Batch #1 received {} lines:", lines.len());
 
                 // Show first few lines as example
                 for (i, line) in lines.iter().take(3).enumerate() {
@@ -47,12 +42,9 @@ async fn basic_usage() -> Result<(), Box<dyn std::error::Error>> {
                 if lines.len() > 3 {
                     println!("    ... and {} more lines", lines.len() - 3);
                 }
-
-                break; // Just show the first batch for demo
             }
             Err(e) => {
                 eprintln!("  ❌ Error: {}", e);
-                break;
             }
         }
     }
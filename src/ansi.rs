@@ -0,0 +1,185 @@
+//! Removal of ANSI escape sequences and control characters from records.
+
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::Chars;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+const RESET: &str = "\x1b[0m";
+
+/// How ANSI escape sequences and control characters in records are handled.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{AnsiMode, StreamConfig};
+///
+/// let config = StreamConfig::new().ansi(AnsiMode::Strip);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnsiMode {
+    /// Leave records as they were written.
+    #[default]
+    Keep,
+    /// Remove CSI and OSC sequences and every control character other than
+    /// tab and newline, leaving plain text.
+    Strip,
+    /// Keep colour and style (SGR) sequences for terminals that support them
+    /// and remove everything else, such as cursor movement, window titles,
+    /// hyperlinks and control characters. A reset is appended to records that
+    /// leave a style active, so colour never bleeds into the next record.
+    Normalize,
+}
+
+impl AnsiMode {
+    /// Returns the record with escape sequences handled according to the mode.
+    pub(crate) fn clean<'a>(&self, record: &'a str) -> Cow<'a, str> {
+        if *self == AnsiMode::Keep || !record.chars().any(is_removable) {
+            return Cow::Borrowed(record);
+        }
+
+        let mut output = String::with_capacity(record.len());
+        let mut styled = false;
+        let mut chars = record.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                ESC => match chars.next() {
+                    Some('[') => {
+                        let (params, end) = read_csi(&mut chars);
+                        if *self == AnsiMode::Normalize && end == Some('m') {
+                            output.push(ESC);
+                            output.push('[');
+                            output.push_str(&params);
+                            output.push('m');
+                            styled = sgr_styled(&params, styled);
+                        }
+                    }
+                    Some(']' | 'P' | 'X' | '^' | '_') => skip_string(&mut chars),
+                    // Two character escapes such as `ESC 7` (save cursor)
+                    _ => {}
+                },
+                c if is_removable(c) => {}
+                c => output.push(c),
+            }
+        }
+        if styled {
+            output.push_str(RESET);
+        }
+        Cow::Owned(output)
+    }
+}
+
+/// Control characters other than tab and newline, including escape.
+fn is_removable(c: char) -> bool {
+    c.is_control() && c != '\t' && c != '\n'
+}
+
+/// Returns whether a style is active after an SGR sequence, evaluating its
+/// parameters in order: `0` or an empty parameter resets, anything else sets.
+/// The arguments of extended colours such as `38;5;0` are not parameters.
+fn sgr_styled(params: &str, mut styled: bool) -> bool {
+    let mut params = params.split(';');
+    while let Some(param) = params.next() {
+        let code = param.trim_start_matches('0');
+        styled = !code.is_empty();
+        if matches!(code, "38" | "48" | "58") {
+            let skip = match params.next() {
+                Some("5") => 1,
+                Some("2") => 3,
+                _ => 0,
+            };
+            params.by_ref().take(skip).for_each(drop);
+        }
+    }
+    styled
+}
+
+/// Reads the rest of a CSI sequence, returning its parameters and final byte.
+fn read_csi(chars: &mut Peekable<Chars<'_>>) -> (String, Option<char>) {
+    let mut params = String::new();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\x30'..='\x3f' => params.push(c),
+            '\x20'..='\x2f' => {}
+            '\x40'..='\x7e' => {
+                chars.next();
+                return (params, Some(c));
+            }
+            // Malformed, leave the rest of the record alone
+            _ => return (params, None),
+        }
+        chars.next();
+    }
+    (params, None)
+}
+
+/// Skips an OSC, DCS, SOS, PM or APC string up to its `BEL` or `ESC \` terminator.
+fn skip_string(chars: &mut Peekable<Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL => return,
+            ESC => {
+                chars.next_if_eq(&'\\');
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep() {
+        let line = "\x1b[31merror\x1b[0m";
+        assert!(matches!(AnsiMode::Keep.clean(line), Cow::Borrowed(_)));
+        assert!(matches!(
+            AnsiMode::Strip.clean("plain\ttext"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_strip() {
+        let cases = [
+            ("\x1b[1;31mERROR\x1b[0m failed", "ERROR failed"),
+            ("\x1b[2K\x1b[1Gprogress 50%", "progress 50%"),
+            ("\x1b]0;title\x07build done", "build done"),
+            (
+                "see \x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\ page",
+                "see docs page",
+            ),
+            ("\x1b7saved\x1b8", "saved"),
+            ("back\x08\x08space\r\x00", "backspace"),
+            ("tab\tkept", "tab\tkept"),
+            ("trailing \x1b[", "trailing "),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(AnsiMode::Strip.clean(line), expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let cases = [
+            (
+                "\x1b[1;31mERROR\x1b[0m failed",
+                "\x1b[1;31mERROR\x1b[0m failed",
+            ),
+            ("\x1b[32mok\x1b[2K", "\x1b[32mok\x1b[0m"),
+            ("\x1b]0;title\x07\x1b[mplain\r", "\x1b[mplain"),
+            ("\x1b[31mred\x1b[00m", "\x1b[31mred\x1b[00m"),
+            ("\x1b[31mred\x1b[1;0m", "\x1b[31mred\x1b[1;0m"),
+            ("\x1b[0;31mred", "\x1b[0;31mred\x1b[0m"),
+            ("\x1b[31mred\x1b[1;m", "\x1b[31mred\x1b[1;m"),
+            ("\x1b[38;5;0mblack", "\x1b[38;5;0mblack\x1b[0m"),
+            ("\x1b[48;2;0;0;0mblack", "\x1b[48;2;0;0;0mblack\x1b[0m"),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(AnsiMode::Normalize.clean(line), expected, "{:?}", line);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Assembly of the records of one Linux audit event into a single record.
+
+use crate::parser::{AuditLine, audit_timestamp, parse_audit_line};
+use crate::record::{FieldValue, Fields, Record};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Configuration for grouping audit log records into events.
+///
+/// auditd writes one event as several lines (`SYSCALL`, `CWD`, `PATH`,
+/// `PROCTITLE`, ...) that share the serial in `msg=audit(time:serial)`. Those
+/// lines are combined into one record whose text holds every line of the
+/// event and whose fields are:
+///
+/// * `timestamp` and `serial` of the event,
+/// * `types`, the record types in the order they were read,
+/// * `records`, the fields of each line as parsed by
+///   [`AuditParser`](crate::AuditParser), including its `type`.
+///
+/// An event is emitted when its `EOE` line is read, or once no line for it has
+/// arrived within the flush timeout, as single record events have no `EOE`.
+/// Lines that are not audit records are passed through unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{AuditConfig, StreamConfig};
+/// use std::time::Duration;
+///
+/// let audit = AuditConfig::new().flush_timeout(Duration::from_millis(500));
+/// let config = StreamConfig::new().audit_events(audit);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    pub(crate) flush_timeout: Duration,
+    pub(crate) max_pending: usize,
+}
+
+impl AuditConfig {
+    /// Default time an event waits for more records before it is emitted.
+    pub const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+    /// Default number of events assembled at the same time.
+    pub const DEFAULT_MAX_PENDING: usize = 256;
+
+    /// Creates a configuration with the default limits.
+    pub fn new() -> Self {
+        Self {
+            flush_timeout: Self::DEFAULT_FLUSH_TIMEOUT,
+            max_pending: Self::DEFAULT_MAX_PENDING,
+        }
+    }
+
+    /// Sets how long an event without `EOE` waits for more records before it is emitted.
+    pub fn flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+
+    /// Sets how many events may be assembled at once; the oldest is emitted beyond that.
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An event whose records are still being read.
+#[derive(Debug)]
+struct PendingEvent {
+    serial: u64,
+    record: Record,
+    types: Vec<FieldValue>,
+    records: Vec<FieldValue>,
+    last_update: Instant,
+}
+
+impl PendingEvent {
+    /// Start an event from its first line, keeping the line's metadata
+    fn new(record: Record, line: AuditLine) -> Self {
+        let mut fields = Fields::new();
+        fields.insert("timestamp", audit_timestamp(&line.timestamp));
+        fields.insert("serial", line.serial as i64);
+
+        let mut event = Self {
+            serial: line.serial,
+            record: Record {
+                text: String::new(),
+                fields,
+                metadata: record.metadata,
+                ..Record::default()
+            },
+            types: Vec::new(),
+            records: Vec::new(),
+            last_update: Instant::now(),
+        };
+        event.add(&record.text, line);
+        event
+    }
+
+    fn add(&mut self, text: &str, line: AuditLine) {
+        if !self.record.text.is_empty() {
+            self.record.text.push('\n');
+        }
+        self.record.text.push_str(text);
+
+        let mut fields = Fields::new();
+        fields.insert("type", line.kind.clone());
+        fields.extend(line.fields);
+        self.types.push(FieldValue::from(line.kind));
+        self.records.push(FieldValue::Map(fields));
+        self.last_update = Instant::now();
+    }
+
+    fn finish(mut self) -> Record {
+        self.record
+            .fields
+            .insert("types", FieldValue::List(self.types));
+        self.record
+            .fields
+            .insert("records", FieldValue::List(self.records));
+        self.record
+    }
+}
+
+/// Groups audit records by event serial according to an [`AuditConfig`].
+#[derive(Debug)]
+pub(crate) struct AuditAssembler {
+    config: AuditConfig,
+    pending: Vec<PendingEvent>,
+}
+
+impl AuditAssembler {
+    pub(crate) fn new(config: AuditConfig) -> Self {
+        Self {
+            config,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Adds records and returns the events they completed, along with lines that are not audit records.
+    pub(crate) fn push_all(&mut self, records: Vec<Record>) -> Vec<Record> {
+        let mut completed = Vec::new();
+        for record in records {
+            self.push(record, &mut completed);
+        }
+        completed
+    }
+
+    /// Emits every event being assembled, oldest first.
+    pub(crate) fn flush(&mut self) -> Vec<Record> {
+        self.pending.drain(..).map(PendingEvent::finish).collect()
+    }
+
+    /// Emits the events that have waited longer than the flush timeout.
+    pub(crate) fn flush_expired(&mut self, now: Instant) -> Vec<Record> {
+        let timeout = self.config.flush_timeout;
+        let (expired, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|event| event.last_update + timeout <= now);
+        self.pending = pending;
+        expired.into_iter().map(PendingEvent::finish).collect()
+    }
+
+    /// Returns when the oldest pending event should be emitted if no more records arrive.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        self.pending
+            .iter()
+            .map(|event| event.last_update + self.config.flush_timeout)
+            .min()
+    }
+
+    fn push(&mut self, record: Record, completed: &mut Vec<Record>) {
+        let Some(line) = parse_audit_line(&record.text) else {
+            completed.push(record);
+            return;
+        };
+
+        let index = self
+            .pending
+            .iter()
+            .position(|event| event.serial == line.serial);
+
+        if line.kind == "EOE" {
+            // The end of event marker only completes the event, it adds nothing to it
+            if let Some(index) = index {
+                completed.push(self.pending.remove(index).finish());
+            }
+            return;
+        }
+
+        match index {
+            Some(index) => self.pending[index].add(&record.text, line),
+            None => {
+                if self.pending.len() >= self.config.max_pending {
+                    completed.push(self.pending.remove(0).finish());
+                }
+                self.pending.push(PendingEvent::new(record, line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSCALL: &str = r#"type=SYSCALL msg=audit(1364481363.243:24287): arch=c000003e syscall=2 success=no exit=-13 comm="cat""#;
+    const CWD: &str = r#"type=CWD msg=audit(1364481363.243:24287): cwd="/home/shadowman""#;
+    const PATH: &str =
+        r#"type=PATH msg=audit(1364481363.243:24287): item=0 name="/etc/ssh/sshd_config""#;
+    const EOE: &str = "type=EOE msg=audit(1364481363.243:24287): ";
+
+    fn records(lines: &[&str]) -> Vec<Record> {
+        lines.iter().map(|line| Record::new(*line)).collect()
+    }
+
+    fn record_types(record: &Record) -> Vec<String> {
+        match record.fields.get("types") {
+            Some(FieldValue::List(types)) => types.iter().map(ToString::to_string).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_event_completed_by_eoe() {
+        let mut assembler = AuditAssembler::new(AuditConfig::new());
+
+        assert!(assembler.push_all(records(&[SYSCALL, CWD])).is_empty());
+        let events = assembler.push_all(records(&[PATH, EOE]));
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.text, [SYSCALL, CWD, PATH].join("\n"));
+        assert_eq!(event.fields.get("serial"), Some(&FieldValue::Int(24287)));
+        assert_eq!(record_types(event), vec!["SYSCALL", "CWD", "PATH"]);
+
+        let Some(FieldValue::List(parts)) = event.fields.get("records") else {
+            panic!("Expected records");
+        };
+        let FieldValue::Map(path) = &parts[2] else {
+            panic!("Expected a map");
+        };
+        assert_eq!(path.get_str("name"), Some("/etc/ssh/sshd_config"));
+        assert!(assembler.flush().is_empty());
+    }
+
+    #[test]
+    fn test_interleaved_events_and_other_lines() {
+        let mut assembler = AuditAssembler::new(AuditConfig::new());
+        let other = "type=USER_END msg=audit(1364481363.250:24288): pid=1 res=success";
+
+        let events = assembler.push_all(records(&[SYSCALL, other, "not audit", CWD, EOE]));
+
+        let texts: Vec<&str> = events.iter().map(|event| event.text.as_str()).collect();
+        assert_eq!(texts, vec!["not audit", &format!("{}\n{}", SYSCALL, CWD)]);
+
+        let rest = assembler.flush();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(record_types(&rest[0]), vec!["USER_END"]);
+    }
+
+    #[test]
+    fn test_max_pending_emits_oldest() {
+        let mut assembler = AuditAssembler::new(AuditConfig::new().max_pending(1));
+        let next = "type=SYSCALL msg=audit(1364481364.000:24290): syscall=59";
+
+        let events = assembler.push_all(records(&[SYSCALL, next]));
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].text, SYSCALL);
+    }
+
+    #[tokio::test]
+    async fn test_flush_expired() {
+        let timeout = Duration::from_millis(50);
+        let mut assembler = AuditAssembler::new(AuditConfig::new().flush_timeout(timeout));
+        assert!(assembler.flush_deadline().is_none());
+
+        assembler.push_all(records(&[SYSCALL]));
+        let deadline = assembler.flush_deadline().unwrap();
+
+        assert!(assembler.flush_expired(deadline - timeout).is_empty());
+        let events = assembler.flush_expired(deadline);
+        assert_eq!(events.len(), 1);
+        assert!(assembler.flush_deadline().is_none());
+    }
+}
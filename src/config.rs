@@ -0,0 +1,405 @@
+//! Configuration for how a log stream splits and filters file content.
+
+use crate::ansi::AnsiMode;
+use crate::audit::AuditConfig;
+use crate::container::ContainerFormat;
+use crate::dedup::DedupConfig;
+use crate::delimited::DelimitedFormat;
+use crate::delimiter::Delimiter;
+use crate::error::Result;
+use crate::filter::RecordFilter;
+use crate::level::LevelConfig;
+use crate::multiline::MultilineConfig;
+use crate::parser::Parser;
+use crate::processor::Pipeline;
+use crate::redact::RedactionConfig;
+use crate::sampling::SamplingConfig;
+use crate::timestamp::TimestampConfig;
+use std::fmt;
+use std::sync::Arc;
+
+/// How records that are empty or contain only whitespace are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyRecordPolicy {
+    /// Emit every record, including empty and whitespace-only ones.
+    KeepAll,
+    /// Drop records that are completely empty, keep whitespace-only ones.
+    DropEmpty,
+    /// Drop records that are empty or contain only whitespace.
+    #[default]
+    DropWhitespace,
+}
+
+impl EmptyRecordPolicy {
+    /// Returns true if a record should be emitted under this policy.
+    pub(crate) fn keeps(&self, record: &str) -> bool {
+        match self {
+            EmptyRecordPolicy::KeepAll => true,
+            EmptyRecordPolicy::DropEmpty => !record.is_empty(),
+            EmptyRecordPolicy::DropWhitespace => !record.trim().is_empty(),
+        }
+    }
+}
+
+/// Options controlling how a [`LogStream`](crate::LogStream) turns file content into records.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{EmptyRecordPolicy, StreamConfig};
+///
+/// let config = StreamConfig::new()
+///     .separator("\n")
+///     .empty_records(EmptyRecordPolicy::KeepAll)
+///     .trim_carriage_return(true);
+/// ```
+#[derive(Clone, Default)]
+pub struct StreamConfig {
+    pub(crate) delimiter: Delimiter,
+    pub(crate) buffer_partial_records: bool,
+    pub(crate) empty_records: EmptyRecordPolicy,
+    pub(crate) trim_carriage_return: bool,
+    pub(crate) ansi: AnsiMode,
+    pub(crate) start_at_end: bool,
+    pub(crate) container_format: Option<ContainerFormat>,
+    pub(crate) delimited: Option<DelimitedFormat>,
+    pub(crate) audit_events: Option<AuditConfig>,
+    pub(crate) multiline: Option<MultilineConfig>,
+    pub(crate) parser: Option<Arc<dyn Parser>>,
+    pub(crate) timestamps: Option<TimestampConfig>,
+    pub(crate) levels: Option<LevelConfig>,
+    pub(crate) filter: Option<RecordFilter>,
+    pub(crate) dedup: Option<DedupConfig>,
+    pub(crate) sampling: Option<SamplingConfig>,
+    pub(crate) pipeline: Option<Pipeline>,
+    pub(crate) redaction: Option<RedactionConfig>,
+}
+
+impl StreamConfig {
+    /// Creates a configuration with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a literal content separator (defaults to newline).
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.delimiter = Delimiter::Literal(separator.into());
+        self
+    }
+
+    /// Sets how content is split into records.
+    pub fn delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Holds back content after the last delimiter until more data arrives.
+    ///
+    /// Without this, a record that is only partially written when the file is read
+    /// is emitted as-is. With it, the unterminated tail is kept and joined with the
+    /// next read; it is flushed when the stream is closed or the file is truncated.
+    /// This is needed for [`Delimiter::Boundary`], where a record is only known to be
+    /// complete once the next one starts.
+    pub fn buffer_partial_records(mut self, buffer: bool) -> Self {
+        self.buffer_partial_records = buffer;
+        self
+    }
+
+    /// Sets how empty and whitespace-only records are handled.
+    pub fn empty_records(mut self, policy: EmptyRecordPolicy) -> Self {
+        self.empty_records = policy;
+        self
+    }
+
+    /// Strips a trailing `\r` from each record so CRLF files produce clean lines.
+    pub fn trim_carriage_return(mut self, trim: bool) -> Self {
+        self.trim_carriage_return = trim;
+        self
+    }
+
+    /// Sets how ANSI escape sequences and control characters in each record are
+    /// handled, for example to strip the colours some tools write into log files.
+    pub fn ansi(mut self, mode: AnsiMode) -> Self {
+        self.ansi = mode;
+        self
+    }
+
+    /// Skips the content already in the file and only emits what is appended later.
+    pub fn start_at_end(mut self, start_at_end: bool) -> Self {
+        self.start_at_end = start_at_end;
+        self
+    }
+
+    /// Unwraps lines written by a container runtime, such as the files under
+    /// `/var/log/containers`, before any multiline joining or parsing.
+    ///
+    /// Partial chunks are reassembled into full lines. Container runtimes always
+    /// terminate lines, so this is best combined with
+    /// [`buffer_partial_records`](Self::buffer_partial_records).
+    pub fn container_format(mut self, format: ContainerFormat) -> Self {
+        self.container_format = Some(format);
+        self
+    }
+
+    /// Parses CSV, TSV or other delimiter separated rows into fields named by a header.
+    pub fn delimited(mut self, format: DelimitedFormat) -> Self {
+        self.delimited = Some(format);
+        self
+    }
+
+    /// Combines the lines of one Linux audit event into a single record.
+    pub fn audit_events(mut self, audit: AuditConfig) -> Self {
+        self.audit_events = Some(audit);
+        self
+    }
+
+    /// Joins related lines, such as stack traces, into a single record.
+    pub fn multiline(mut self, multiline: MultilineConfig) -> Self {
+        self.multiline = Some(multiline);
+        self
+    }
+
+    /// Parses every record into fields, available when reading with
+    /// [`LogStream::into_records`](crate::LogStream::into_records).
+    ///
+    /// Records that already have fields, such as the columns from
+    /// [`delimited`](Self::delimited) or an event from
+    /// [`audit_events`](Self::audit_events), keep them and the parsed fields
+    /// are added, replacing any with the same name.
+    pub fn parser(mut self, parser: impl Parser + 'static) -> Self {
+        self.parser = Some(Arc::new(parser));
+        self
+    }
+
+    /// Sets [`Record::timestamp`](crate::Record::timestamp) to the event time
+    /// found in the parsed fields or the text of each record.
+    pub fn timestamps(mut self, timestamps: TimestampConfig) -> Self {
+        self.timestamps = Some(timestamps);
+        self
+    }
+
+    /// Sets [`Record::level`](crate::Record::level) on each record and drops
+    /// the ones below the minimum level, if one is set.
+    pub fn levels(mut self, levels: LevelConfig) -> Self {
+        self.levels = Some(levels);
+        self
+    }
+
+    /// Drops records rejected by the filter before they are sent to the stream.
+    pub fn filter(mut self, filter: RecordFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Collapses runs of repeated records into one "last message repeated" record.
+    pub fn dedup(mut self, dedup: DedupConfig) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    /// Samples and rate limits records, emitting summaries of the dropped ones.
+    pub fn sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Runs each record through the processors of the pipeline, after
+    /// filtering and redaction.
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Masks or hashes secrets and personal data in each record before it is sent to the stream.
+    pub fn redaction(mut self, redaction: RedactionConfig) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Checks settings the builder cannot reject, before a stream starts.
+    pub(crate) fn validate(&self) -> Result<()> {
+        self.delimiter.validate()?;
+        #[cfg(not(feature = "json"))]
+        if self.container_format == Some(ContainerFormat::Docker) {
+            return Err(crate::error::Error::InvalidFormat {
+                message: "the Docker container format requires the `json` feature".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for StreamConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamConfig")
+            .field("delimiter", &self.delimiter)
+            .field("buffer_partial_records", &self.buffer_partial_records)
+            .field("empty_records", &self.empty_records)
+            .field("trim_carriage_return", &self.trim_carriage_return)
+            .field("ansi", &self.ansi)
+            .field("start_at_end", &self.start_at_end)
+            .field("container_format", &self.container_format)
+            .field("delimited", &self.delimited)
+            .field("audit_events", &self.audit_events)
+            .field("multiline", &self.multiline)
+            .field("parser", &self.parser.as_ref().map(|_| "Parser"))
+            .field("timestamps", &self.timestamps)
+            .field("levels", &self.levels)
+            .field("filter", &self.filter)
+            .field("dedup", &self.dedup)
+            .field("sampling", &self.sampling)
+            .field("pipeline", &self.pipeline)
+            .field("redaction", &self.redaction)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::parser::LogfmtParser;
+
+    #[test]
+    fn test_default_config() {
+        let config = StreamConfig::default();
+        assert!(matches!(config.delimiter, Delimiter::Literal(ref s) if s == "\n"));
+        assert!(!config.buffer_partial_records);
+        assert_eq!(config.empty_records, EmptyRecordPolicy::DropWhitespace);
+        assert!(!config.trim_carriage_return);
+        assert_eq!(config.ansi, AnsiMode::Keep);
+        assert!(config.multiline.is_none());
+        assert!(config.parser.is_none());
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = StreamConfig::new()
+            .separator("|")
+            .buffer_partial_records(true)
+            .empty_records(EmptyRecordPolicy::DropEmpty)
+            .trim_carriage_return(true)
+            .ansi(AnsiMode::Strip);
+
+        assert!(matches!(config.delimiter, Delimiter::Literal(ref s) if s == "|"));
+        assert!(config.buffer_partial_records);
+        assert_eq!(config.empty_records, EmptyRecordPolicy::DropEmpty);
+        assert!(config.trim_carriage_return);
+        assert_eq!(config.ansi, AnsiMode::Strip);
+    }
+
+    #[test]
+    fn test_config_delimiter() {
+        let config = StreamConfig::new().delimiter(Delimiter::any_of(["\r\n", "\n"]).unwrap());
+        assert!(matches!(config.delimiter, Delimiter::AnyOf(ref s) if s.len() == 2));
+    }
+
+    #[test]
+    fn test_config_multiline() {
+        let config = StreamConfig::new().multiline(MultilineConfig::continuation_whitespace());
+        assert!(config.multiline.is_some());
+    }
+
+    #[cfg(not(feature = "json"))]
+    #[test]
+    fn test_docker_format_requires_json_feature() {
+        let config = StreamConfig::new().container_format(ContainerFormat::Docker);
+        assert!(matches!(
+            config.validate(),
+            Err(crate::error::Error::InvalidFormat { .. })
+        ));
+        assert!(StreamConfig::new().validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_container_format() {
+        let config = StreamConfig::new().container_format(ContainerFormat::Cri);
+        assert_eq!(config.container_format, Some(ContainerFormat::Cri));
+    }
+
+    #[test]
+    fn test_config_delimited() {
+        let config = StreamConfig::new()
+            .start_at_end(true)
+            .delimited(DelimitedFormat::csv());
+        assert!(config.start_at_end);
+        assert_eq!(config.delimited, Some(DelimitedFormat::csv()));
+    }
+
+    #[test]
+    fn test_config_audit_events() {
+        let config = StreamConfig::new().audit_events(AuditConfig::new().max_pending(8));
+        assert_eq!(config.audit_events.map(|audit| audit.max_pending), Some(8));
+    }
+
+    #[test]
+    fn test_config_parser() {
+        let config = StreamConfig::new().parser(LogfmtParser::new());
+        assert!(config.parser.is_some());
+        assert!(format!("{:?}", config).contains("parser: Some"));
+    }
+
+    #[test]
+    fn test_config_timestamps() {
+        let config = StreamConfig::new().timestamps(TimestampConfig::new().format("%H:%M:%S"));
+        assert_eq!(
+            config.timestamps.map(|timestamps| timestamps.formats),
+            Some(vec!["%H:%M:%S".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_config_levels() {
+        let config = StreamConfig::new().levels(LevelConfig::new().min_level(Level::Warn));
+        assert_eq!(
+            config.levels.and_then(|levels| levels.min_level),
+            Some(Level::Warn)
+        );
+    }
+
+    #[test]
+    fn test_config_filter() {
+        let config = StreamConfig::new().filter(RecordFilter::new().include(["ERROR"]).unwrap());
+        assert!(config.filter.is_some());
+        assert!(format!("{:?}", config).contains("ERROR"));
+    }
+
+    #[test]
+    fn test_config_dedup() {
+        let config = StreamConfig::new().dedup(DedupConfig::new().normalize(true));
+        assert_eq!(config.dedup, Some(DedupConfig::new().normalize(true)));
+    }
+
+    #[test]
+    fn test_config_sampling() {
+        let config = StreamConfig::new().sampling(SamplingConfig::new().max_per_second(100));
+        assert_eq!(config.sampling.unwrap().max_per_second, Some(100));
+    }
+
+    #[test]
+    fn test_config_pipeline() {
+        let config = StreamConfig::new().pipeline(Pipeline::new().processor(|record| vec![record]));
+        assert!(config.pipeline.is_some());
+        assert!(format!("{:?}", config).contains("processors: 1"));
+    }
+
+    #[test]
+    fn test_config_redaction() {
+        let config = StreamConfig::new().redaction(RedactionConfig::new());
+        assert!(config.redaction.is_some());
+        assert!(format!("{:?}", config).contains("redaction: Some"));
+    }
+
+    #[test]
+    fn test_empty_record_policy_keeps() {
+        assert!(EmptyRecordPolicy::KeepAll.keeps(""));
+        assert!(EmptyRecordPolicy::KeepAll.keeps("  "));
+
+        assert!(!EmptyRecordPolicy::DropEmpty.keeps(""));
+        assert!(EmptyRecordPolicy::DropEmpty.keeps("  "));
+
+        assert!(!EmptyRecordPolicy::DropWhitespace.keeps(""));
+        assert!(!EmptyRecordPolicy::DropWhitespace.keeps(" \t "));
+        assert!(EmptyRecordPolicy::DropWhitespace.keeps(" a "));
+    }
+}
@@ -0,0 +1,377 @@
+//! Decoders for the log formats written by container runtimes.
+
+use crate::record::{Fields, Record};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How container runtime log lines are wrapped.
+///
+/// Decoding unwraps each line to the message the container wrote and
+/// reassembles lines the runtime split into partial chunks. The output stream
+/// (`stdout` or `stderr`) and the runtime timestamp are kept in
+/// [`Record::metadata`] under `stream` and `time`.
+///
+/// A line whose final chunk never arrives, for example because the container
+/// crashed, is emitted as it is once it reaches
+/// [`MAX_PARTIAL_BYTES`](Self::MAX_PARTIAL_BYTES) or no chunk has arrived for
+/// [`PARTIAL_TIMEOUT`](Self::PARTIAL_TIMEOUT).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerFormat {
+    /// Docker json-file lines: `{"log":"...\n","stream":"stdout","time":"..."}`.
+    ///
+    /// Requires the `json` feature, streams using it fail to start without it.
+    Docker,
+    /// Kubernetes CRI lines: `<time> <stream> <P|F> <message>`.
+    Cri,
+    /// Detect the format of each line. Lines in neither format pass through unchanged.
+    Auto,
+}
+
+impl ContainerFormat {
+    /// Longest a line is reassembled to before it is emitted without its final chunk.
+    pub const MAX_PARTIAL_BYTES: usize = 1024 * 1024;
+    /// How long a line waits for its next chunk before it is emitted without it.
+    pub const PARTIAL_TIMEOUT: Duration = Duration::from_secs(1);
+}
+
+/// One line unwrapped from the runtime format.
+struct ContainerLine {
+    stream: String,
+    time: String,
+    message: String,
+    partial: bool,
+}
+
+/// A line being reassembled from partial chunks of one output stream.
+#[derive(Debug)]
+struct PartialLine {
+    stream: String,
+    text: String,
+    metadata: Fields,
+    last_update: Instant,
+}
+
+impl From<PartialLine> for Record {
+    fn from(partial: PartialLine) -> Self {
+        Record {
+            metadata: partial.metadata,
+            ..Record::new(partial.text)
+        }
+    }
+}
+
+/// Unwraps container runtime lines and joins partial chunks.
+#[derive(Debug)]
+pub(crate) struct ContainerDecoder {
+    format: ContainerFormat,
+    partials: Vec<PartialLine>,
+}
+
+impl ContainerDecoder {
+    pub(crate) fn new(format: ContainerFormat) -> Self {
+        Self {
+            format,
+            partials: Vec::new(),
+        }
+    }
+
+    /// Decodes records, holding back partial chunks until their line is complete.
+    pub(crate) fn decode_all(&mut self, records: Vec<Record>) -> Vec<Record> {
+        records
+            .into_iter()
+            .filter_map(|record| self.decode(record))
+            .collect()
+    }
+
+    /// Emits lines that are still waiting for their final chunk.
+    pub(crate) fn flush(&mut self) -> Vec<Record> {
+        self.partials.drain(..).map(Record::from).collect()
+    }
+
+    /// Emits lines whose next chunk has not arrived within the timeout.
+    pub(crate) fn flush_expired(&mut self, now: Instant) -> Vec<Record> {
+        let (expired, waiting) = std::mem::take(&mut self.partials)
+            .into_iter()
+            .partition(|partial| partial.last_update + ContainerFormat::PARTIAL_TIMEOUT <= now);
+        self.partials = waiting;
+        expired.into_iter().map(Record::from).collect::<Vec<_>>()
+    }
+
+    /// Returns when the oldest incomplete line should be emitted if no more chunks arrive.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        self.partials
+            .iter()
+            .map(|partial| partial.last_update + ContainerFormat::PARTIAL_TIMEOUT)
+            .min()
+    }
+
+    fn decode(&mut self, record: Record) -> Option<Record> {
+        let Some(line) = self.unwrap_line(&record.text) else {
+            return Some(record);
+        };
+
+        let index = self
+            .partials
+            .iter()
+            .position(|partial| partial.stream == line.stream);
+
+        if line.partial {
+            let index = match index {
+                Some(index) => {
+                    let partial = &mut self.partials[index];
+                    partial.text.push_str(&line.message);
+                    partial.last_update = Instant::now();
+                    index
+                }
+                None => {
+                    self.partials.push(PartialLine {
+                        metadata: line_metadata(&line),
+                        stream: line.stream,
+                        text: line.message,
+                        last_update: Instant::now(),
+                    });
+                    self.partials.len() - 1
+                }
+            };
+            // Emit an overlong line rather than growing it without bound
+            if self.partials[index].text.len() >= ContainerFormat::MAX_PARTIAL_BYTES {
+                return Some(self.partials.remove(index).into());
+            }
+            return None;
+        }
+
+        let (text, metadata) = match index {
+            Some(index) => {
+                let mut partial = self.partials.remove(index);
+                partial.text.push_str(&line.message);
+                (partial.text, partial.metadata)
+            }
+            None => {
+                let metadata = line_metadata(&line);
+                (line.message, metadata)
+            }
+        };
+
+        Some(Record {
+            metadata,
+            ..Record::new(text)
+        })
+    }
+
+    fn unwrap_line(&self, text: &str) -> Option<ContainerLine> {
+        match self.format {
+            #[cfg(feature = "json")]
+            ContainerFormat::Docker => unwrap_docker(text),
+            // Rejected when the stream starts
+            #[cfg(not(feature = "json"))]
+            ContainerFormat::Docker => None,
+            ContainerFormat::Cri => unwrap_cri(text),
+            #[cfg(feature = "json")]
+            ContainerFormat::Auto if text.starts_with('{') => unwrap_docker(text),
+            ContainerFormat::Auto => unwrap_cri(text),
+        }
+    }
+}
+
+fn line_metadata(line: &ContainerLine) -> Fields {
+    let mut metadata = Fields::new();
+    if !line.stream.is_empty() {
+        metadata.insert("stream", line.stream.as_str());
+    }
+    if !line.time.is_empty() {
+        metadata.insert("time", line.time.as_str());
+    }
+    metadata
+}
+
+/// Unwrap a CRI line, where a `P` tag marks a partial chunk
+fn unwrap_cri(text: &str) -> Option<ContainerLine> {
+    let (time, rest) = text.split_once(' ')?;
+    let (stream, rest) = rest.split_once(' ')?;
+    let (tag, message) = rest.split_once(' ').unwrap_or((rest, ""));
+
+    if !time.starts_with(|c: char| c.is_ascii_digit()) || !time.contains('T') {
+        return None;
+    }
+    if stream != "stdout" && stream != "stderr" {
+        return None;
+    }
+    // Further tags may follow the partial flag, separated by `:`
+    let partial = match tag.split(':').next()? {
+        "P" => true,
+        "F" => false,
+        _ => return None,
+    };
+
+    Some(ContainerLine {
+        stream: stream.to_string(),
+        time: time.to_string(),
+        message: message.to_string(),
+        partial,
+    })
+}
+
+/// Unwrap a Docker json-file line, where a log without a trailing newline is a partial chunk
+#[cfg(feature = "json")]
+fn unwrap_docker(text: &str) -> Option<ContainerLine> {
+    #[derive(serde::Deserialize)]
+    struct DockerLine {
+        log: String,
+        #[serde(default)]
+        stream: String,
+        #[serde(default)]
+        time: String,
+    }
+
+    let DockerLine { log, stream, time } = serde_json::from_str(text).ok()?;
+    let (message, partial) = match log.strip_suffix('\n') {
+        Some(message) => (message.to_string(), false),
+        None => (log, true),
+    };
+
+    Some(ContainerLine {
+        stream,
+        time,
+        message,
+        partial,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(decoder: &mut ContainerDecoder, lines: &[&str]) -> Vec<Record> {
+        decoder.decode_all(lines.iter().map(|line| Record::new(*line)).collect())
+    }
+
+    #[test]
+    fn test_cri_lines_and_metadata() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Cri);
+        let records = decode(
+            &mut decoder,
+            &[
+                "2024-01-01T00:00:00.000000001Z stdout F hello world",
+                "2024-01-01T00:00:01Z stderr F ",
+            ],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "hello world");
+        assert_eq!(records[0].metadata.get_str("stream"), Some("stdout"));
+        assert_eq!(
+            records[0].metadata.get_str("time"),
+            Some("2024-01-01T00:00:00.000000001Z")
+        );
+        assert_eq!(records[1].text, "");
+        assert_eq!(records[1].metadata.get_str("stream"), Some("stderr"));
+    }
+
+    #[test]
+    fn test_cri_partial_reassembly_per_stream() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Cri);
+        let records = decode(
+            &mut decoder,
+            &[
+                "2024-01-01T00:00:00Z stdout P first ",
+                "2024-01-01T00:00:00Z stderr F error line",
+                "2024-01-01T00:00:01Z stdout P second ",
+            ],
+        );
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "error line");
+
+        let records = decode(&mut decoder, &["2024-01-01T00:00:02Z stdout F third"]);
+        assert_eq!(records[0].text, "first second third");
+        // The timestamp of the first chunk is kept
+        assert_eq!(
+            records[0].metadata.get_str("time"),
+            Some("2024-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_flush_emits_incomplete_lines() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Cri);
+        assert!(decode(&mut decoder, &["2024-01-01T00:00:00Z stdout P cut "]).is_empty());
+
+        let records = decoder.flush();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "cut ");
+        assert!(decoder.flush().is_empty());
+    }
+
+    #[test]
+    fn test_overlong_partial_line_is_emitted() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Cri);
+        let chunk = format!(
+            "2024-01-01T00:00:00Z stdout P {}",
+            "x".repeat(ContainerFormat::MAX_PARTIAL_BYTES / 2)
+        );
+
+        assert!(decode(&mut decoder, &[chunk.as_str()]).is_empty());
+        let records = decode(&mut decoder, &[chunk.as_str()]);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text.len(), ContainerFormat::MAX_PARTIAL_BYTES);
+        assert!(decoder.flush_deadline().is_none());
+
+        // The final chunk is a line of its own
+        let records = decode(&mut decoder, &["2024-01-01T00:00:01Z stdout F end"]);
+        assert_eq!(records[0].text, "end");
+    }
+
+    #[tokio::test]
+    async fn test_partial_timeout() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Cri);
+        let start = Instant::now();
+        decode(&mut decoder, &["2024-01-01T00:00:00Z stdout P cut "]);
+
+        let deadline = decoder.flush_deadline().unwrap();
+        assert!(deadline >= start + ContainerFormat::PARTIAL_TIMEOUT);
+        assert!(
+            decoder
+                .flush_expired(deadline - Duration::from_millis(1))
+                .is_empty()
+        );
+
+        let records = decoder.flush_expired(deadline);
+        assert_eq!(records[0].text, "cut ");
+        assert!(decoder.flush_deadline().is_none());
+    }
+
+    #[test]
+    fn test_unrecognised_lines_pass_through() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Auto);
+        let records = decode(
+            &mut decoder,
+            &["plain line", "2024 stdout F not a timestamp"],
+        );
+
+        assert_eq!(records[0].text, "plain line");
+        assert_eq!(records[1].text, "2024 stdout F not a timestamp");
+        assert!(records[0].metadata.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_docker_lines_and_partials() {
+        let mut decoder = ContainerDecoder::new(ContainerFormat::Auto);
+        let records = decode(
+            &mut decoder,
+            &[
+                r#"{"log":"part one, ","stream":"stdout","time":"2024-01-01T00:00:00Z"}"#,
+                r#"{"log":"part two\n","stream":"stdout","time":"2024-01-01T00:00:01Z"}"#,
+                r#"{"log":"\"quoted\"\n","stream":"stderr","time":"2024-01-01T00:00:02Z"}"#,
+            ],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "part one, part two");
+        assert_eq!(
+            records[0].metadata.get_str("time"),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(records[1].text, "\"quoted\"");
+        assert_eq!(records[1].metadata.get_str("stream"), Some("stderr"));
+    }
+}
@@ -0,0 +1,251 @@
+//! Collapsing of repeated records into a single "last message repeated" record.
+
+use crate::record::Record;
+use regex::Regex;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Parts of a line that vary between otherwise identical messages: UUIDs,
+/// hex values and words made of hex digits that contain a number.
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b|\b0x[0-9a-f]+\b|\b[0-9a-f]*\d[0-9a-f]*\b",
+    )
+    .unwrap()
+});
+
+/// Configuration for collapsing consecutive repeated records.
+///
+/// Like syslog, the first record of a run of identical records is emitted
+/// straight away. The repeats that follow within the window are held back and
+/// replaced by one `last message repeated N times` record, emitted when a
+/// different record arrives or the window ends. It keeps the fields, level and
+/// timestamp of the last repeat and adds `repeat_count` and `repeated_text`.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{DedupConfig, StreamConfig};
+/// use std::time::Duration;
+///
+/// let dedup = DedupConfig::new()
+///     .window(Duration::from_secs(10))
+///     .normalize(true);
+/// let config = StreamConfig::new().dedup(dedup);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupConfig {
+    pub(crate) window: Duration,
+    pub(crate) normalize: bool,
+}
+
+impl DedupConfig {
+    /// Default time from the first record of a run during which repeats are collapsed.
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+
+    /// Creates a configuration that collapses exact repeats within the default window.
+    pub fn new() -> Self {
+        Self {
+            window: Self::DEFAULT_WINDOW,
+            normalize: false,
+        }
+    }
+
+    /// Sets how long after the first record of a run repeats are collapsed.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets whether numbers, hex values and UUIDs are ignored when comparing
+    /// records, so `retry 1 of 5` and `retry 2 of 5` count as repeats.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The current run of identical records.
+#[derive(Debug)]
+struct Run {
+    key: String,
+    started: Instant,
+    repeats: u64,
+    last: Option<Record>,
+}
+
+impl Run {
+    /// The summary of the held back repeats, if there were any
+    fn finish(self) -> Option<Record> {
+        let mut record = self.last?;
+        let text = std::mem::take(&mut record.text);
+        record.fields.insert("repeat_count", self.repeats as i64);
+        record.fields.insert("repeated_text", text);
+        record.text = format!("last message repeated {} times", self.repeats);
+        Some(record)
+    }
+}
+
+/// Collapses repeated records according to a [`DedupConfig`].
+#[derive(Debug)]
+pub(crate) struct Deduplicator {
+    config: DedupConfig,
+    run: Option<Run>,
+    suppressed: u64,
+}
+
+impl Deduplicator {
+    pub(crate) fn new(config: DedupConfig) -> Self {
+        Self {
+            config,
+            run: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Adds a record, returning the summary of the run it ends and the record
+    /// itself unless it repeats the current run.
+    pub(crate) fn push(
+        &mut self,
+        record: Record,
+        now: Instant,
+    ) -> (Option<Record>, Option<Record>) {
+        let key = if self.config.normalize {
+            VARIABLE.replace_all(&record.text, "#").into_owned()
+        } else {
+            record.text.clone()
+        };
+
+        if let Some(run) = &mut self.run
+            && run.key == key
+            && now < run.started + self.config.window
+        {
+            run.repeats += 1;
+            run.last = Some(record);
+            self.suppressed += 1;
+            return (None, None);
+        }
+
+        let summary = self.flush();
+        self.run = Some(Run {
+            key,
+            started: now,
+            repeats: 0,
+            last: None,
+        });
+        (summary, Some(record))
+    }
+
+    /// Emits the summary of the current run, if it has repeats.
+    pub(crate) fn flush(&mut self) -> Option<Record> {
+        self.run.take().and_then(Run::finish)
+    }
+
+    /// Returns when the summary of the current run is due if no other record arrives.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        self.run
+            .as_ref()
+            .filter(|run| run.repeats > 0)
+            .map(|run| run.started + self.config.window)
+    }
+
+    /// Returns how many records have been collapsed into summaries so far.
+    pub(crate) fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// Emits the summary of the current run if its window has ended.
+    pub(crate) fn flush_expired(&mut self, now: Instant) -> Option<Record> {
+        if self.flush_deadline()? <= now {
+            self.flush()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(dedup: &mut Deduplicator, lines: &[&str], now: Instant) -> Vec<String> {
+        let mut output: Vec<Record> = dedup.flush_expired(now).into_iter().collect();
+        for line in lines {
+            let (summary, record) = dedup.push(Record::new(*line), now);
+            output.extend(summary.into_iter().chain(record));
+        }
+        output.into_iter().map(|record| record.text).collect()
+    }
+
+    #[test]
+    fn test_collapses_consecutive_repeats() {
+        let mut dedup = Deduplicator::new(DedupConfig::new());
+        let now = Instant::now();
+
+        let output = push(&mut dedup, &["a", "b", "b", "b", "c", "b"], now);
+        assert_eq!(
+            output,
+            vec!["a", "b", "last message repeated 2 times", "c", "b"]
+        );
+        assert_eq!(dedup.suppressed(), 2);
+        assert!(dedup.flush().is_none());
+    }
+
+    #[test]
+    fn test_summary_keeps_the_last_repeat() {
+        let mut dedup = Deduplicator::new(DedupConfig::new().normalize(true));
+        let now = Instant::now();
+
+        let output = push(
+            &mut dedup,
+            &[
+                "retry 1 of 5 for 5f0c6a5e-8f3b-4c43-9d4e-7a8c9f1b2d3e",
+                "retry 2 of 5 for 0b9d2c4e-1a2b-4c3d-8e9f-0a1b2c3d4e5f",
+                "retry 3 of 5 for 0x7ffe",
+            ],
+            now,
+        );
+        assert_eq!(output.len(), 1);
+
+        let summary = dedup.flush().unwrap();
+        assert_eq!(summary.text, "last message repeated 2 times");
+        assert_eq!(
+            summary.fields.get("repeat_count").unwrap().as_i64(),
+            Some(2)
+        );
+        assert_eq!(
+            summary.fields.get_str("repeated_text"),
+            Some("retry 3 of 5 for 0x7ffe")
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let window = Duration::from_secs(10);
+        let mut dedup = Deduplicator::new(DedupConfig::new().window(window));
+        let start = Instant::now();
+
+        assert_eq!(push(&mut dedup, &["x", "x"], start), vec!["x"]);
+        assert_eq!(dedup.flush_deadline(), Some(start + window));
+
+        // Nothing arrives before the window ends, the summary is emitted with the next batch
+        let later = start + window;
+        assert_eq!(
+            push(&mut dedup, &[], later),
+            vec!["last message repeated 1 times"]
+        );
+        assert!(dedup.flush_deadline().is_none());
+
+        // A repeat after the window starts a new run
+        assert_eq!(push(&mut dedup, &["x"], later), vec!["x"]);
+        assert_eq!(push(&mut dedup, &["x"], later + window), vec!["x"]);
+        assert!(dedup.flush_deadline().is_none());
+    }
+}
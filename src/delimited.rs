@@ -0,0 +1,380 @@
+//! Parsing of CSV, TSV and other delimiter separated logs with a header row.
+
+use crate::record::{Fields, Record};
+
+/// Most physical lines a quoted field may span before the row is given up on.
+const MAX_ROW_LINES: usize = 1000;
+
+/// Where the column names of a delimited log come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Header {
+    /// The first row of the file names the columns.
+    #[default]
+    FirstRow,
+    /// The first row names the columns if it looks like a header: every cell is
+    /// non-empty, not a number and unique. Otherwise it is treated as data.
+    Detect,
+    /// Fixed column names; every row is data.
+    Names(Vec<String>),
+    /// No column names; cells are stored as `column1`, `column2`, ...
+    None,
+}
+
+/// Settings for parsing delimiter separated records into fields.
+///
+/// Each row becomes a record whose fields map column names to cell values, kept
+/// as strings. Quoting follows RFC 4180: quoted cells may contain the delimiter,
+/// doubled quotes and line breaks, in which case the physical lines of the row
+/// are joined back into a single record. The header row itself is not emitted.
+///
+/// The header is read from the start of the file even when the stream starts
+/// at the end, and read again after the file is truncated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelimitedFormat {
+    pub(crate) delimiter: char,
+    pub(crate) quote: Option<char>,
+    pub(crate) header: Header,
+}
+
+impl DelimitedFormat {
+    /// Comma separated values with `"` quoting.
+    pub fn csv() -> Self {
+        Self::new(',')
+    }
+
+    /// Tab separated values without quoting.
+    pub fn tsv() -> Self {
+        Self {
+            quote: None,
+            ..Self::new('\t')
+        }
+    }
+
+    /// Values separated by `delimiter`, with `"` quoting.
+    pub fn new(delimiter: char) -> Self {
+        Self {
+            delimiter,
+            quote: Some('"'),
+            header: Header::default(),
+        }
+    }
+
+    /// Sets the quote character, or disables quoting with `None`.
+    pub fn quote(mut self, quote: Option<char>) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets where the column names come from.
+    pub fn header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+/// A row whose quoted cell continues on the next line.
+#[derive(Debug)]
+struct PendingRow {
+    record: Record,
+    lines: usize,
+}
+
+/// Splits records into cells, joining rows with line breaks inside quotes.
+#[derive(Debug)]
+pub(crate) struct DelimitedDecoder {
+    format: DelimitedFormat,
+    columns: Option<Vec<String>>,
+    header_resolved: bool,
+    pending: Option<PendingRow>,
+}
+
+impl DelimitedDecoder {
+    pub(crate) fn new(format: DelimitedFormat) -> Self {
+        let mut decoder = Self {
+            format,
+            columns: None,
+            header_resolved: false,
+            pending: None,
+        };
+        decoder.reset_header();
+        decoder
+    }
+
+    /// Returns true until the header row has been seen or ruled out.
+    pub(crate) fn needs_header(&self) -> bool {
+        !self.header_resolved
+    }
+
+    /// Forgets a header read from the file, so the next row is checked again.
+    pub(crate) fn reset_header(&mut self) {
+        match &self.format.header {
+            Header::Names(names) => {
+                self.columns = Some(names.clone());
+                self.header_resolved = true;
+            }
+            Header::None => {
+                self.columns = None;
+                self.header_resolved = true;
+            }
+            Header::FirstRow | Header::Detect => {
+                self.columns = None;
+                self.header_resolved = false;
+            }
+        }
+    }
+
+    /// Resolves the header from the first lines of the file without emitting anything.
+    pub(crate) fn read_header(&mut self, lines: Vec<String>) {
+        let mut pending: Option<String> = None;
+        for line in lines {
+            let row = match pending.take() {
+                Some(mut row) => {
+                    row.push('\n');
+                    row.push_str(&line);
+                    row
+                }
+                None => line,
+            };
+            match split_row(&row, self.format.delimiter, self.format.quote) {
+                Some(cells) => {
+                    self.resolve_header(cells);
+                    return;
+                }
+                None => pending = Some(row),
+            }
+        }
+    }
+
+    /// Adds records and returns the complete data rows with their fields.
+    pub(crate) fn push_all(&mut self, records: Vec<Record>) -> Vec<Record> {
+        let mut rows = Vec::new();
+        for record in records {
+            rows.extend(self.push(record));
+        }
+        rows
+    }
+
+    /// Emits a row still waiting for its closing quote, without fields.
+    pub(crate) fn flush(&mut self) -> Option<Record> {
+        self.pending.take().map(|pending| pending.record)
+    }
+
+    fn push(&mut self, record: Record) -> Option<Record> {
+        let mut pending = match self.pending.take() {
+            Some(mut pending) => {
+                pending.record.text.push('\n');
+                pending.record.text.push_str(&record.text);
+                pending.lines += 1;
+                pending
+            }
+            None => PendingRow { record, lines: 1 },
+        };
+
+        let Some(cells) = split_row(
+            &pending.record.text,
+            self.format.delimiter,
+            self.format.quote,
+        ) else {
+            if pending.lines >= MAX_ROW_LINES {
+                return Some(pending.record);
+            }
+            self.pending = Some(pending);
+            return None;
+        };
+
+        if !self.header_resolved && self.resolve_header(cells.clone()) {
+            return None;
+        }
+
+        pending.record.fields = self.to_fields(cells);
+        Some(pending.record)
+    }
+
+    /// Decide whether the first row is a header, returning true if it was
+    fn resolve_header(&mut self, cells: Vec<String>) -> bool {
+        self.header_resolved = true;
+        let is_header = match self.format.header {
+            Header::FirstRow => true,
+            Header::Detect => looks_like_header(&cells),
+            Header::Names(_) | Header::None => false,
+        };
+        if is_header {
+            self.columns = Some(cells);
+        }
+        is_header
+    }
+
+    fn to_fields(&self, cells: Vec<String>) -> Fields {
+        let columns = self.columns.as_deref().unwrap_or_default();
+        cells
+            .into_iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let name = columns
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| format!("column{}", index + 1));
+                (name, cell)
+            })
+            .collect()
+    }
+}
+
+/// A header row has only unique, non-empty, non-numeric cells
+fn looks_like_header(cells: &[String]) -> bool {
+    cells.iter().enumerate().all(|(index, cell)| {
+        let cell = cell.trim();
+        !cell.is_empty()
+            && cell.parse::<f64>().is_err()
+            && !cells[..index].iter().any(|other| other.trim() == cell)
+    })
+}
+
+/// Split a row into cells, returning `None` if a quoted cell is not closed yet
+fn split_row(row: &str, delimiter: char, quote: Option<char>) -> Option<Vec<String>> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = row.chars().peekable();
+    let mut at_cell_start = true;
+    let mut in_quotes = false;
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if Some(c) == quote {
+                if chars.peek() == quote.as_ref() {
+                    cell.push(c);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                cell.push(c);
+            }
+        } else if c == delimiter {
+            cells.push(std::mem::take(&mut cell));
+            at_cell_start = true;
+            continue;
+        } else if at_cell_start && Some(c) == quote {
+            in_quotes = true;
+        } else {
+            cell.push(c);
+        }
+        at_cell_start = false;
+    }
+
+    if in_quotes {
+        return None;
+    }
+    cells.push(cell);
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(decoder: &mut DelimitedDecoder, lines: &[&str]) -> Vec<Record> {
+        decoder.push_all(lines.iter().map(|line| Record::new(*line)).collect())
+    }
+
+    #[test]
+    fn test_split_row_rfc4180() {
+        assert_eq!(
+            split_row(r#"a,"b,c","say ""hi""",,"#, ',', Some('"')),
+            Some(vec![
+                "a".to_string(),
+                "b,c".to_string(),
+                r#"say "hi""#.to_string(),
+                String::new(),
+                String::new(),
+            ])
+        );
+        // Quotes inside an unquoted cell are literal
+        assert_eq!(
+            split_row(r#"5" disk,ok"#, ',', Some('"')),
+            Some(vec![r#"5" disk"#.to_string(), "ok".to_string()])
+        );
+        assert_eq!(split_row(r#"a,"open"#, ',', Some('"')), None);
+        assert_eq!(
+            split_row("a\t\"b\"", '\t', None),
+            Some(vec!["a".to_string(), "\"b\"".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_header_row_names_fields() {
+        let mut decoder = DelimitedDecoder::new(DelimitedFormat::csv());
+        let records = rows(&mut decoder, &["time,level,msg", "10:00,INFO,started"]);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "10:00,INFO,started");
+        assert_eq!(records[0].fields.get_str("level"), Some("INFO"));
+        assert_eq!(records[0].fields.get_str("msg"), Some("started"));
+    }
+
+    #[test]
+    fn test_embedded_newlines_are_joined() {
+        let mut decoder = DelimitedDecoder::new(DelimitedFormat::csv());
+        let records = rows(
+            &mut decoder,
+            &["id,msg", "1,\"first line", "second line\"", "2,done"],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "1,\"first line\nsecond line\"");
+        assert_eq!(
+            records[0].fields.get_str("msg"),
+            Some("first line\nsecond line")
+        );
+        assert_eq!(records[1].fields.get_str("id"), Some("2"));
+
+        assert!(rows(&mut decoder, &["3,\"unterminated"]).is_empty());
+        assert_eq!(decoder.flush().unwrap().text, "3,\"unterminated");
+    }
+
+    #[test]
+    fn test_header_detection() {
+        let mut decoder = DelimitedDecoder::new(DelimitedFormat::tsv().header(Header::Detect));
+        let records = rows(&mut decoder, &["1700000000\tok", "1700000001\tfail"]);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].fields.get_str("column1"), Some("1700000000"));
+
+        let mut decoder = DelimitedDecoder::new(DelimitedFormat::tsv().header(Header::Detect));
+        let records = rows(&mut decoder, &["ts\tstatus", "1700000000\tok"]);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields.get_str("status"), Some("ok"));
+    }
+
+    #[test]
+    fn test_read_header_and_reset() {
+        let mut decoder = DelimitedDecoder::new(DelimitedFormat::csv());
+        decoder.read_header(vec![
+            "a,\"b".to_string(),
+            "c\"".to_string(),
+            "1,2".to_string(),
+        ]);
+        assert!(!decoder.needs_header());
+
+        let records = rows(&mut decoder, &["3,4,5"]);
+        assert_eq!(records[0].fields.get_str("b\nc"), Some("4"));
+        assert_eq!(records[0].fields.get_str("column3"), Some("5"));
+
+        decoder.reset_header();
+        assert!(decoder.needs_header());
+        assert!(rows(&mut decoder, &["x,y"]).is_empty());
+        assert_eq!(
+            rows(&mut decoder, &["1,2"])[0].fields.get_str("y"),
+            Some("2")
+        );
+    }
+
+    #[test]
+    fn test_fixed_names() {
+        let format = DelimitedFormat::csv().header(Header::Names(vec!["k".into(), "v".into()]));
+        let mut decoder = DelimitedDecoder::new(format);
+        assert!(!decoder.needs_header());
+
+        let records = rows(&mut decoder, &["a,1"]);
+        assert_eq!(records[0].fields.get_str("k"), Some("a"));
+    }
+}
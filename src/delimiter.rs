@@ -0,0 +1,258 @@
+//! Record delimiters used to split file content into records.
+
+use crate::error::{Error, Result};
+use regex::Regex;
+
+/// Describes where one record ends and the next begins.
+#[derive(Debug, Clone)]
+pub enum Delimiter {
+    /// Records are separated by a literal string, which is removed.
+    Literal(String),
+    /// Records are separated by any of several literal strings, which are removed.
+    ///
+    /// When more than one separator matches at the same position the longest wins,
+    /// so `["\r\n", "\n"]` handles mixed line endings.
+    AnyOf(Vec<String>),
+    /// Each match of the regex marks the start of a new record and is kept as part of it.
+    ///
+    /// This suits logs where every record starts with a timestamp or similar prefix.
+    /// A single trailing newline is removed from each record.
+    Boundary(Regex),
+}
+
+impl Delimiter {
+    /// Creates a delimiter that splits on a literal string, which must not be empty.
+    pub fn literal(separator: impl Into<String>) -> Result<Self> {
+        let delimiter = Delimiter::Literal(separator.into());
+        delimiter.validate()?;
+        Ok(delimiter)
+    }
+
+    /// Creates a delimiter that splits on any of the given literal strings,
+    /// none of which may be empty.
+    pub fn any_of<I, S>(separators: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let delimiter = Delimiter::AnyOf(separators.into_iter().map(Into::into).collect());
+        delimiter.validate()?;
+        Ok(delimiter)
+    }
+
+    /// Creates a delimiter where each match of `pattern` starts a new record.
+    pub fn boundary(pattern: &str) -> Result<Self> {
+        Ok(Delimiter::Boundary(Regex::new(pattern)?))
+    }
+
+    /// Checks that the delimiter can split content, rejecting empty separators
+    /// that would never advance.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let separators = match self {
+            Delimiter::Literal(separator) => std::slice::from_ref(separator),
+            Delimiter::AnyOf(separators) => separators.as_slice(),
+            Delimiter::Boundary(_) => return Ok(()),
+        };
+        if separators.is_empty() || separators.iter().any(String::is_empty) {
+            return Err(Error::InvalidFormat {
+                message: "record separators must not be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Splits content into complete records and the remainder after the last delimiter.
+    ///
+    /// The remainder is returned untouched so it can be prepended to the next read.
+    pub(crate) fn split<'a>(&self, content: &'a str) -> (Vec<&'a str>, &'a str) {
+        match self {
+            Delimiter::Literal(separator) => {
+                split_literal(content, std::slice::from_ref(separator))
+            }
+            Delimiter::AnyOf(separators) => split_literal(content, separators),
+            Delimiter::Boundary(regex) => split_boundary(content, regex),
+        }
+    }
+
+    /// Removes delimiter-specific framing from a record before it is emitted.
+    pub(crate) fn trim_record<'a>(&self, record: &'a str) -> &'a str {
+        match self {
+            Delimiter::Boundary(_) => record.strip_suffix('\n').unwrap_or(record),
+            _ => record,
+        }
+    }
+}
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Delimiter::Literal("\n".to_string())
+    }
+}
+
+impl From<String> for Delimiter {
+    fn from(separator: String) -> Self {
+        Delimiter::Literal(separator)
+    }
+}
+
+impl From<&str> for Delimiter {
+    fn from(separator: &str) -> Self {
+        Delimiter::Literal(separator.to_string())
+    }
+}
+
+/// Split on the earliest of the separators, preferring the longest at the same position
+///
+/// The next match of each separator is remembered and only searched for again once
+/// it has been passed, so the content is scanned once per separator.
+fn split_literal<'a>(content: &'a str, separators: &[String]) -> (Vec<&'a str>, &'a str) {
+    let mut next: Vec<Option<usize>> = separators
+        .iter()
+        .map(|separator| content.find(separator.as_str()))
+        .collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    while let Some((index, len)) = next
+        .iter()
+        .zip(separators)
+        .filter_map(|(index, separator)| index.map(|index| (index, separator.len())))
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+    {
+        parts.push(&content[start..index]);
+        start = index + len;
+
+        for (index, separator) in next.iter_mut().zip(separators) {
+            if let Some(found) = *index
+                && found < start
+            {
+                *index = content[start..]
+                    .find(separator.as_str())
+                    .map(|found| start + found);
+            }
+        }
+    }
+
+    (parts, &content[start..])
+}
+
+/// Split so that every regex match starts a new record
+fn split_boundary<'a>(content: &'a str, regex: &Regex) -> (Vec<&'a str>, &'a str) {
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    for found in regex.find_iter(content) {
+        if found.start() > start {
+            parts.push(&content[start..found.start()]);
+        }
+        start = found.start();
+    }
+
+    (parts, &content[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_split() {
+        let delimiter = Delimiter::literal("\n").unwrap();
+        assert_eq!(delimiter.split("a\nb\nc"), (vec!["a", "b"], "c"));
+        assert_eq!(delimiter.split("a\nb\n"), (vec!["a", "b"], ""));
+        assert_eq!(delimiter.split(""), (vec![], ""));
+        assert_eq!(delimiter.split("\n\n"), (vec!["", ""], ""));
+    }
+
+    #[test]
+    fn test_literal_multi_char_split() {
+        let delimiter = Delimiter::literal("<<>>").unwrap();
+        assert_eq!(delimiter.split("a<<>>b<<>>c"), (vec!["a", "b"], "c"));
+    }
+
+    #[test]
+    fn test_empty_separator_rejected() {
+        assert!(matches!(
+            Delimiter::literal(""),
+            Err(Error::InvalidFormat { .. })
+        ));
+        assert!(Delimiter::any_of(["\n", ""]).is_err());
+        assert!(Delimiter::any_of(Vec::<String>::new()).is_err());
+        assert!(Delimiter::from("").validate().is_err());
+    }
+
+    #[test]
+    fn test_any_of_split() {
+        let delimiter = Delimiter::any_of(["\n", "|"]).unwrap();
+        assert_eq!(delimiter.split("a|b\nc|"), (vec!["a", "b", "c"], ""));
+    }
+
+    #[test]
+    fn test_any_of_prefers_longest_at_same_position() {
+        let delimiter = Delimiter::any_of(["\n", "\r\n"]).unwrap();
+        assert_eq!(delimiter.split("a\r\nb\nc\r\n"), (vec!["a", "b", "c"], ""));
+    }
+
+    #[test]
+    fn test_any_of_large_buffer_with_unused_separator() {
+        let delimiter = Delimiter::any_of(["\r\n", "\n"]).unwrap();
+        let content = "a log line of some length\n".repeat(200_000);
+
+        let (parts, rest) = delimiter.split(&content);
+        assert_eq!(parts.len(), 200_000);
+        assert!(
+            parts
+                .iter()
+                .all(|part| *part == "a log line of some length")
+        );
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn test_boundary_split() {
+        let delimiter = Delimiter::boundary(r"(?m)^\d{4}-\d{2}-\d{2} ").unwrap();
+        let content = "2024-01-01 first\n  continued\n2024-01-02 second\n";
+
+        let (parts, rest) = delimiter.split(content);
+        assert_eq!(parts, vec!["2024-01-01 first\n  continued\n"]);
+        assert_eq!(rest, "2024-01-02 second\n");
+    }
+
+    #[test]
+    fn test_boundary_split_keeps_leading_text() {
+        let delimiter = Delimiter::boundary(r"(?m)^\[").unwrap();
+        let (parts, rest) = delimiter.split("orphan\n[a]\n[b]");
+        assert_eq!(parts, vec!["orphan\n", "[a]\n"]);
+        assert_eq!(rest, "[b]");
+    }
+
+    #[test]
+    fn test_boundary_without_match() {
+        let delimiter = Delimiter::boundary(r"(?m)^START").unwrap();
+        assert_eq!(delimiter.split("no match"), (vec![], "no match"));
+    }
+
+    #[test]
+    fn test_boundary_invalid_pattern() {
+        assert!(Delimiter::boundary("(unclosed").is_err());
+    }
+
+    #[test]
+    fn test_trim_record() {
+        let boundary = Delimiter::boundary("x").unwrap();
+        assert_eq!(boundary.trim_record("record\n"), "record");
+        assert_eq!(boundary.trim_record("record"), "record");
+        assert_eq!(
+            Delimiter::literal("|").unwrap().trim_record("record\n"),
+            "record\n"
+        );
+    }
+
+    #[test]
+    fn test_from_string() {
+        match Delimiter::from("|") {
+            Delimiter::Literal(separator) => assert_eq!(separator, "|"),
+            other => panic!("Expected literal delimiter, got {:?}", other),
+        }
+    }
+}
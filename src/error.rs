@@ -17,6 +17,22 @@ pub enum Error {
     #[error("UTF-8 decoding error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
 
+    /// Invalid regular expression in a delimiter, filter or parser.
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+
+    /// Invalid log format or pattern given to a parser.
+    #[error("Invalid log format: {message}")]
+    InvalidFormat { message: String },
+
+    /// Filter expression that does not parse.
+    #[error("Invalid filter expression at position {position}: {message}")]
+    InvalidExpression { position: usize, message: String },
+
+    /// Script that fails to load or compile.
+    #[error("Script error: {message}")]
+    Script { message: String },
+
     /// File path errors.
     #[error("Invalid file path: {message}")]
     InvalidPath { message: String },
@@ -28,6 +44,10 @@ pub enum Error {
     /// Stream has been closed or dropped.
     #[error("Stream closed")]
     StreamClosed,
+
+    /// The background reader task panicked or was cancelled.
+    #[error("Reader task failed: {message}")]
+    TaskFailed { message: String },
 }
 
 /// A convenient Result type for log reader operations.
@@ -79,6 +99,20 @@ mod tests {
         assert!(error.to_string().contains("UTF-8 decoding error"));
     }
 
+    #[test]
+    fn test_regex_error_conversion() {
+        let pattern = String::from("(unclosed");
+        let regex_error = regex::Regex::new(&pattern).unwrap_err();
+        let error: Error = regex_error.into();
+
+        match error {
+            Error::Regex(_) => {}
+            _ => panic!("Expected Error::Regex variant"),
+        }
+
+        assert!(error.to_string().contains("Invalid regular expression"));
+    }
+
     #[test]
     fn test_invalid_path_error() {
         let error = Error::InvalidPath {
@@ -109,6 +143,42 @@ mod tests {
         assert_eq!(error.to_string(), "Stream closed");
     }
 
+    #[test]
+    fn test_task_failed_error() {
+        let error = Error::TaskFailed {
+            message: "task panicked".to_string(),
+        };
+        assert_eq!(error.to_string(), "Reader task failed: task panicked");
+    }
+
+    #[test]
+    fn test_invalid_format_error() {
+        let error = Error::InvalidFormat {
+            message: "no variables".to_string(),
+        };
+        assert_eq!(error.to_string(), "Invalid log format: no variables");
+    }
+
+    #[test]
+    fn test_invalid_expression_error() {
+        let error = Error::InvalidExpression {
+            position: 6,
+            message: "expected a value".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Invalid filter expression at position 6: expected a value"
+        );
+    }
+
+    #[test]
+    fn test_script_error() {
+        let error = Error::Script {
+            message: "missing fn process".to_string(),
+        };
+        assert_eq!(error.to_string(), "Script error: missing fn process");
+    }
+
     #[test]
     fn test_error_debug_format() {
         let error = Error::StreamClosed;
@@ -123,7 +193,7 @@ mod tests {
 
         assert!(success.is_ok());
         assert!(failure.is_err());
-        assert_eq!(success.unwrap(), 42);
+        assert_eq!(success.ok(), Some(42));
 
         match failure {
             Err(Error::StreamClosed) => {}
@@ -0,0 +1,745 @@
+//! A small expression language for filtering structured records.
+
+use crate::error::{Error, Result};
+use crate::level::Level;
+use crate::record::{FieldValue, Fields, Record};
+use crate::timestamp::parse_timestamp;
+use chrono::{DateTime, FixedOffset};
+use regex::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A filter over records written as a string, such as
+/// `level >= warn && service == "api" && msg =~ /timeout/ && latency_ms > 500`.
+///
+/// # Syntax
+///
+/// * Fields are referred to by name. Nested map values use dots (`http.status`)
+///   and names not found in the fields are looked up in the record metadata.
+///   `text` is the record text, `level` the detected level (or the `level`
+///   field) and `timestamp` the event time (or the `timestamp` field).
+/// * Literals are `"strings"` or `'strings'`, numbers, `true`, `false` and
+///   `null`. Level names such as `warn` can be used bare when compared with `level`.
+/// * Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=`, plus `=~` and `!~`
+///   against a `/regex/` (with an optional `i` flag for case-insensitive) or a
+///   string holding one.
+/// * Conditions combine with `&&`/`and`, `||`/`or`, `!`/`not` and parentheses.
+///   A field on its own is true when it is present and not `false`, `0`, `""` or `null`.
+///
+/// Comparisons are typed: a number compared with a string field that holds a
+/// number compares numerically, levels compare by severity and timestamps
+/// compare as points in time, parsing string literals in the common formats.
+/// A comparison with a missing field is false, except `!=` and `!~`, which are
+/// the negation of `==` and `=~`.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{FilterExpr, Record};
+///
+/// let filter: FilterExpr = r#"status >= 500 and path =~ /^\/api/"#.parse().unwrap();
+///
+/// let mut record = Record::new("request");
+/// record.fields.insert("status", "503");
+/// record.fields.insert("path", "/api/users");
+/// assert!(filter.matches(&record));
+/// ```
+#[derive(Clone)]
+pub struct FilterExpr {
+    source: String,
+    root: Node,
+}
+
+impl FilterExpr {
+    /// Parses an expression.
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = ExprParser {
+            tokens,
+            index: 0,
+            end: source.len(),
+            depth: 0,
+        };
+        let root = parser.or()?;
+        if let Some((_, position)) = parser.peek_token() {
+            return Err(invalid(position, "unexpected input after the expression"));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// Returns true if the record matches the expression.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.root.eval(record)
+    }
+
+    /// Returns the expression as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl FromStr for FilterExpr {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl fmt::Debug for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FilterExpr").field(&self.source).finish()
+    }
+}
+
+fn invalid(position: usize, message: impl Into<String>) -> Error {
+    Error::InvalidExpression {
+        position,
+        message: message.into(),
+    }
+}
+
+/// A value taken from a record or written in the expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Level(Level),
+    Timestamp(DateTime<FixedOffset>),
+}
+
+impl Value {
+    fn from_field(value: &FieldValue) -> Value {
+        match value {
+            FieldValue::Null => Value::Null,
+            FieldValue::Bool(value) => Value::Bool(*value),
+            FieldValue::Int(value) => Value::Int(*value),
+            FieldValue::Float(value) => Value::Float(*value),
+            FieldValue::String(value) => Value::String(value.clone()),
+            FieldValue::Timestamp(value) => Value::Timestamp(*value),
+            FieldValue::List(_) | FieldValue::Map(_) => Value::String(value.to_string()),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(value) => *value,
+            Value::Int(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::String(value) => !value.is_empty(),
+            Value::Level(_) | Value::Timestamp(_) => true,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            Value::String(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_level(&self) -> Option<Level> {
+        match self {
+            Value::Level(level) => Some(*level),
+            Value::String(name) => Level::from_name(name),
+            _ => None,
+        }
+    }
+
+    fn as_timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Value::Timestamp(timestamp) => Some(*timestamp),
+            Value::String(text) => parse_timestamp(text),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            Value::String(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn to_text(&self) -> Option<String> {
+        match self {
+            Value::Null => None,
+            Value::Bool(value) => Some(value.to_string()),
+            Value::Int(value) => Some(value.to_string()),
+            Value::Float(value) => Some(value.to_string()),
+            Value::String(value) => Some(value.clone()),
+            Value::Level(level) => Some(level.to_string()),
+            Value::Timestamp(timestamp) => Some(timestamp.to_rfc3339()),
+        }
+    }
+}
+
+/// Order two values by the most specific type either of them has
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    use Value::*;
+
+    match (left, right) {
+        (Null, Null) => Some(Ordering::Equal),
+        (Null, _) | (_, Null) => None,
+        (Level(_), _) | (_, Level(_)) => Some(left.as_level()?.cmp(&right.as_level()?)),
+        (Timestamp(_), _) | (_, Timestamp(_)) => {
+            Some(left.as_timestamp()?.cmp(&right.as_timestamp()?))
+        }
+        (Int(left), Int(right)) => Some(left.cmp(right)),
+        (Int(_) | Float(_), _) | (_, Int(_) | Float(_)) => {
+            left.as_f64()?.partial_cmp(&right.as_f64()?)
+        }
+        (Bool(_), _) | (_, Bool(_)) => Some(left.as_bool()?.cmp(&right.as_bool()?)),
+        (String(left), String(right)) => Some(left.cmp(right)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn apply(self, left: &Value, right: &Value) -> bool {
+        let ordering = compare(left, right);
+        match self {
+            CompareOp::Eq => ordering == Some(Ordering::Equal),
+            CompareOp::Ne => ordering != Some(Ordering::Equal),
+            CompareOp::Lt => ordering == Some(Ordering::Less),
+            CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            CompareOp::Gt => ordering == Some(Ordering::Greater),
+            CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Something that evaluates to a value.
+#[derive(Debug, Clone)]
+enum Operand {
+    Field(String),
+    Literal(Value),
+}
+
+impl Operand {
+    fn eval(&self, record: &Record) -> Value {
+        match self {
+            Operand::Field(name) => resolve(record, name).unwrap_or(Value::Null),
+            Operand::Literal(value) => value.clone(),
+        }
+    }
+}
+
+/// Look up a name in the record, trying the special names first
+fn resolve(record: &Record, name: &str) -> Option<Value> {
+    match name {
+        "text" => Some(Value::String(record.text.clone())),
+        "level" => record
+            .level
+            .map(Value::Level)
+            .or_else(|| lookup(&record.fields, name)),
+        "timestamp" => record
+            .timestamp
+            .map(Value::Timestamp)
+            .or_else(|| lookup(&record.fields, name)),
+        _ => lookup(&record.fields, name).or_else(|| lookup(&record.metadata, name)),
+    }
+}
+
+/// Find a field by its full name, or by following a dotted path into nested maps
+fn lookup(fields: &Fields, name: &str) -> Option<Value> {
+    if let Some(value) = fields.get(name) {
+        return Some(Value::from_field(value));
+    }
+
+    let (first, rest) = name.split_once('.')?;
+    match fields.get(first)? {
+        FieldValue::Map(nested) => lookup(nested, rest),
+        _ => None,
+    }
+}
+
+/// A node of the parsed expression.
+#[derive(Debug, Clone)]
+enum Node {
+    Or(Vec<Node>),
+    And(Vec<Node>),
+    Not(Box<Node>),
+    Compare(Operand, CompareOp, Operand),
+    Match(Operand, Regex),
+    Truthy(Operand),
+}
+
+impl Node {
+    fn eval(&self, record: &Record) -> bool {
+        match self {
+            Node::Or(nodes) => nodes.iter().any(|node| node.eval(record)),
+            Node::And(nodes) => nodes.iter().all(|node| node.eval(record)),
+            Node::Not(node) => !node.eval(record),
+            Node::Compare(left, op, right) => op.apply(&left.eval(record), &right.eval(record)),
+            Node::Match(operand, regex) => operand
+                .eval(record)
+                .to_text()
+                .is_some_and(|text| regex.is_match(&text)),
+            Node::Truthy(operand) => operand.eval(record).is_truthy(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    String(String),
+    Number(Value),
+    Regex(String, bool),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Compare(CompareOp),
+    Match,
+    NotMatch,
+}
+
+/// Split the expression into tokens along with their byte positions
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let rest = &source[position..];
+        let symbol = [
+            ("&&", Token::And),
+            ("||", Token::Or),
+            ("==", Token::Compare(CompareOp::Eq)),
+            ("!=", Token::Compare(CompareOp::Ne)),
+            ("<=", Token::Compare(CompareOp::Le)),
+            (">=", Token::Compare(CompareOp::Ge)),
+            ("=~", Token::Match),
+            ("!~", Token::NotMatch),
+            ("<", Token::Compare(CompareOp::Lt)),
+            (">", Token::Compare(CompareOp::Gt)),
+            ("!", Token::Not),
+            ("(", Token::LParen),
+            (")", Token::RParen),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| rest.starts_with(symbol));
+        if let Some((symbol, token)) = symbol {
+            tokens.push((token, position));
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            continue;
+        }
+
+        let token = match c {
+            '"' | '\'' => {
+                chars.next();
+                Token::String(read_quoted(&mut chars, c, position)?)
+            }
+            '/' => {
+                chars.next();
+                let pattern = read_quoted(&mut chars, '/', position)?;
+                let ignore_case = chars.next_if(|&(_, c)| c == 'i').is_some();
+                Token::Regex(pattern, ignore_case)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let end = rest
+                    .find(|c: char| {
+                        !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+                    })
+                    .unwrap_or(rest.len());
+                let text = &rest[..end];
+                let number = text
+                    .parse()
+                    .map(Value::Int)
+                    .or_else(|_| text.parse().map(Value::Float))
+                    .map_err(|_| invalid(position, format!("invalid number '{}'", text)))?;
+                for _ in text.chars() {
+                    chars.next();
+                }
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' || c == '@' => {
+                let end = rest
+                    .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '@')))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                for _ in word.chars() {
+                    chars.next();
+                }
+                match word {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(word.to_string()),
+                }
+            }
+            _ => return Err(invalid(position, format!("unexpected character '{}'", c))),
+        };
+        tokens.push((token, position));
+    }
+
+    Ok(tokens)
+}
+
+/// Read up to the closing `quote`, unescaping `\quote` and `\\`
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    quote: char,
+    start: usize,
+) -> Result<String> {
+    let mut text = String::new();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, next)) if next == quote || (next == '\\' && quote != '/') => {
+                    text.push(next)
+                }
+                Some((_, next)) => {
+                    text.push('\\');
+                    text.push(next);
+                }
+                None => break,
+            },
+            c if c == quote => return Ok(text),
+            c => text.push(c),
+        }
+    }
+    Err(invalid(start, format!("missing closing {}", quote)))
+}
+
+/// How deeply parentheses and `!` may nest. Chains of `&&` and `||` are kept
+/// flat, so together this keeps a hostile expression from overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Recursive descent parser over the tokens.
+struct ExprParser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize,
+    /// Parentheses and negations currently open.
+    depth: usize,
+}
+
+impl ExprParser {
+    /// Consume a token that opens a nested expression
+    fn enter(&mut self) -> Result<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid(
+                self.position(),
+                format!("nested more than {} deep", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        self.next();
+        Ok(())
+    }
+
+    fn peek_token(&self) -> Option<(&Token, usize)> {
+        self.tokens
+            .get(self.index)
+            .map(|(token, position)| (token, *position))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_token().map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.peek_token().map_or(self.end, |(_, position)| position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            nodes.push(self.and()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::Or(nodes),
+        })
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.not()?];
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            nodes.push(self.not()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::And(nodes),
+        })
+    }
+
+    fn not(&mut self) -> Result<Node> {
+        if self.peek() == Some(&Token::Not) {
+            self.enter()?;
+            let node = Node::Not(Box::new(self.not()?));
+            self.depth -= 1;
+            return Ok(node);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Node> {
+        if self.peek() == Some(&Token::LParen) {
+            self.enter()?;
+            let node = self.or()?;
+            if self.next() != Some(Token::RParen) {
+                return Err(invalid(self.end, "missing closing )"));
+            }
+            self.depth -= 1;
+            return Ok(node);
+        }
+
+        let left = self.operand()?;
+        match self.peek().cloned() {
+            Some(Token::Compare(op)) => {
+                self.next();
+                let right = self.operand()?;
+                let (left, right) = level_literals(left, right);
+                Ok(Node::Compare(left, op, right))
+            }
+            Some(token @ (Token::Match | Token::NotMatch)) => {
+                self.next();
+                let position = self.position();
+                let regex = match self.next() {
+                    Some(Token::Regex(pattern, ignore_case)) => RegexBuilder::new(&pattern)
+                        .case_insensitive(ignore_case)
+                        .build()?,
+                    Some(Token::String(pattern)) => Regex::new(&pattern)?,
+                    _ => return Err(invalid(position, "expected a /regex/ or string")),
+                };
+                let node = Node::Match(left, regex);
+                Ok(match token {
+                    Token::NotMatch => Node::Not(Box::new(node)),
+                    _ => node,
+                })
+            }
+            _ => Ok(Node::Truthy(left)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(match name.as_str() {
+                "true" => Operand::Literal(Value::Bool(true)),
+                "false" => Operand::Literal(Value::Bool(false)),
+                "null" => Operand::Literal(Value::Null),
+                _ => Operand::Field(name),
+            }),
+            Some(Token::String(text)) => Ok(Operand::Literal(Value::String(text))),
+            Some(Token::Number(number)) => Ok(Operand::Literal(number)),
+            _ => Err(invalid(position, "expected a field or value")),
+        }
+    }
+}
+
+/// Treat a bare level name compared with `level` as a level rather than a field
+fn level_literals(left: Operand, right: Operand) -> (Operand, Operand) {
+    let as_level = |operand: &Operand| match operand {
+        Operand::Field(name) => Level::from_name(name),
+        Operand::Literal(_) => None,
+    };
+
+    match (&left, &right) {
+        (Operand::Field(name), _) if name == "level" => match as_level(&right) {
+            Some(level) => (left, Operand::Literal(Value::Level(level))),
+            None => (left, right),
+        },
+        (_, Operand::Field(name)) if name == "level" => match as_level(&left) {
+            Some(level) => (Operand::Literal(Value::Level(level)), right),
+            None => (left, right),
+        },
+        _ => (left, right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Record {
+        let mut record = Record::new("GET /api/users timed out");
+        record.level = Some(Level::Error);
+        record.fields.insert("service", "api");
+        record.fields.insert("msg", "upstream timeout");
+        record.fields.insert("latency_ms", "750");
+        record.fields.insert("status", 504);
+        record.fields.insert("cached", false);
+
+        let mut http = Fields::new();
+        http.insert("method", "GET");
+        record.fields.insert("http", FieldValue::Map(http));
+        record.metadata.insert("stream", "stderr");
+        record
+    }
+
+    fn matches(expression: &str) -> bool {
+        FilterExpr::parse(expression)
+            .unwrap_or_else(|e| panic!("{}: {}", expression, e))
+            .matches(&record())
+    }
+
+    #[test]
+    fn test_example_expression() {
+        assert!(matches(
+            r#"level >= warn && service == "api" && msg =~ /timeout/ && latency_ms > 500"#
+        ));
+        assert!(!matches(r#"level >= fatal && service == "api""#));
+    }
+
+    #[test]
+    fn test_typed_comparisons() {
+        // Numbers against numeric strings compare numerically
+        assert!(matches("latency_ms > 500 and latency_ms < 1000"));
+        assert!(matches("latency_ms == 750.0"));
+        assert!(matches("status >= 500"));
+        assert!(matches(r#"status == "504""#));
+        // Levels compare by severity, also against strings
+        assert!(matches(r#"level > "warning" and level != info"#));
+        assert!(matches("cached == false"));
+        assert!(matches(r#"service < "b""#));
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let mut record = record();
+        record.timestamp = parse_timestamp("2026-10-17T12:00:00Z");
+        let expr =
+            FilterExpr::parse(r#"timestamp >= "2026-10-17 11:00:00" && timestamp < '2026-10-18'"#);
+        // The date only literal does not parse as a timestamp, so the comparison is false
+        assert!(!expr.unwrap().matches(&record));
+
+        let expr = FilterExpr::parse(r#"timestamp > "2026-10-17T13:00:00+02:00""#).unwrap();
+        assert!(expr.matches(&record));
+    }
+
+    #[test]
+    fn test_fields_paths_and_missing_values() {
+        assert!(matches(r#"http.method == "GET""#));
+        assert!(matches(r#"stream == "stderr""#));
+        assert!(matches(r#"text =~ /^get/i"#));
+        assert!(matches("service and !cached"));
+        assert!(matches("missing == null and not missing"));
+        assert!(matches(r#"missing != "x" and missing !~ /x/"#));
+        assert!(!matches("missing > 1 or missing < 1"));
+    }
+
+    #[test]
+    fn test_precedence_and_grouping() {
+        assert!(matches(
+            r#"service == "web" || service == "api" && status == 504"#
+        ));
+        assert!(!matches(
+            r#"(service == "web" || service == "api") && status == 200"#
+        ));
+        assert!(matches(r#"!(status == 200)"#));
+        assert!(matches(r#"msg =~ "time(out)?" and msg !~ /refused/"#));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |expression: &str| match FilterExpr::parse(expression) {
+            Err(Error::InvalidExpression { position, .. }) => position,
+            other => panic!("{}: {:?}", expression, other),
+        };
+
+        assert_eq!(error("level >="), 8);
+        assert_eq!(error("a == 1 b"), 7);
+        assert_eq!(error("(a == 1"), 7);
+        assert_eq!(error(r#"msg == "open"#), 7);
+        assert_eq!(error("a =~ 5"), 5);
+        assert_eq!(error("a # b"), 2);
+        assert!(matches!(
+            FilterExpr::parse("a =~ /(/"),
+            Err(Error::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| {
+            format!(
+                "{}{}a == 1{}",
+                "!".repeat(depth / 2),
+                "(".repeat(depth - depth / 2),
+                ")".repeat(depth - depth / 2)
+            )
+        };
+        assert!(FilterExpr::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            FilterExpr::parse(&nested(MAX_DEPTH + 1)),
+            Err(Error::InvalidExpression { .. })
+        ));
+        assert!(matches!(
+            FilterExpr::parse(&"(".repeat(100_000)),
+            Err(Error::InvalidExpression { position: 64, .. })
+        ));
+        assert!(FilterExpr::parse(&"!".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_long_flat_chain() {
+        let chain = |op: &str| vec!["status == 1"; 100_000].join(op);
+        assert!(
+            !FilterExpr::parse(&chain(" || "))
+                .unwrap()
+                .matches(&record())
+        );
+        assert!(
+            !FilterExpr::parse(&chain(" && "))
+                .unwrap()
+                .matches(&record())
+        );
+
+        let chain = format!("{} || status == 504", chain(" && "));
+        assert!(FilterExpr::parse(&chain).unwrap().matches(&record()));
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        let expr: FilterExpr = "level >= warn".parse().unwrap();
+        assert_eq!(expr.to_string(), "level >= warn");
+        assert_eq!(expr.as_str(), "level >= warn");
+        assert_eq!(format!("{:?}", expr), r#"FilterExpr("level >= warn")"#);
+    }
+}
@@ -0,0 +1,415 @@
+//! Filters that decide which records a stream emits.
+
+use crate::error::Result;
+use crate::expr::FilterExpr;
+use crate::record::Record;
+use regex::RegexSet;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+
+/// A custom filter predicate.
+type Predicate = Arc<dyn Fn(&Record) -> bool + Send + Sync>;
+
+/// A set of regular expressions together with the patterns it was built from.
+#[derive(Debug, Clone)]
+struct PatternSet {
+    patterns: Vec<String>,
+    set: RegexSet,
+}
+
+impl PatternSet {
+    /// Build a set from `existing` patterns plus new ones
+    fn extend(
+        existing: Option<PatternSet>,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<PatternSet> {
+        let mut all = existing.map(|set| set.patterns).unwrap_or_default();
+        all.extend(patterns.into_iter().map(Into::into));
+        Ok(PatternSet {
+            set: RegexSet::new(&all)?,
+            patterns: all,
+        })
+    }
+}
+
+/// Where a record stands relative to the filter when context lines are configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchContext {
+    /// The record passed the filter.
+    Match,
+    /// The record was rejected but comes shortly before a match.
+    Before,
+    /// The record was rejected but comes shortly after a match.
+    After,
+    /// A separator between groups of records that are not adjacent in the file.
+    Separator,
+}
+
+/// Decides which records are emitted, run in the reader task before records
+/// are sent so that dropped records never reach the stream.
+///
+/// A record is kept when it matches at least one include pattern (if any are
+/// set), matches no exclude pattern and every predicate returns true. Patterns
+/// match the full record text, after multiline joining; predicates also see
+/// the parsed fields, timestamp and level. Batches left empty are not sent.
+///
+/// Like `grep -B/-A`, [`before`](Self::before) and [`after`](Self::after)
+/// also emit rejected records around each match, with
+/// [`Record::context`](crate::Record::context) telling them apart, and a
+/// `--` separator record between groups that are not adjacent. Context lines
+/// and separators are never collapsed by deduplication or dropped by sampling.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{RecordFilter, StreamConfig};
+///
+/// # fn main() -> log_reader::Result<()> {
+/// let filter = RecordFilter::new()
+///     .include(["ERROR", "WARN"])?
+///     .exclude([r"GET /health"])?
+///     .predicate(|record| record.text.len() < 10_000)
+///     .context(2);
+/// let config = StreamConfig::new().filter(filter);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RecordFilter {
+    include: Option<PatternSet>,
+    exclude: Option<PatternSet>,
+    predicates: Vec<Predicate>,
+    before: usize,
+    after: usize,
+    separator: Option<String>,
+}
+
+impl RecordFilter {
+    /// Separator emitted between groups of context by default.
+    pub const DEFAULT_SEPARATOR: &str = "--";
+
+    /// Creates a filter that keeps every record.
+    pub fn new() -> Self {
+        Self {
+            include: None,
+            exclude: None,
+            predicates: Vec::new(),
+            before: 0,
+            after: 0,
+            separator: Some(Self::DEFAULT_SEPARATOR.to_string()),
+        }
+    }
+
+    /// Keeps only records matching at least one of these or earlier include patterns.
+    pub fn include(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self> {
+        self.include = Some(PatternSet::extend(self.include.take(), patterns)?);
+        Ok(self)
+    }
+
+    /// Drops records matching any of these patterns.
+    pub fn exclude(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self> {
+        self.exclude = Some(PatternSet::extend(self.exclude.take(), patterns)?);
+        Ok(self)
+    }
+
+    /// Keeps only records for which `predicate` returns true.
+    pub fn predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Record) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Keeps only records matching a [`FilterExpr`] such as `level >= warn && service == "api"`.
+    pub fn expression(self, expression: &str) -> Result<Self> {
+        let expression = FilterExpr::parse(expression)?;
+        Ok(self.predicate(move |record| expression.matches(record)))
+    }
+
+    /// Also emits up to `count` rejected records before each match.
+    pub fn before(mut self, count: usize) -> Self {
+        self.before = count;
+        self
+    }
+
+    /// Also emits up to `count` rejected records after each match.
+    pub fn after(mut self, count: usize) -> Self {
+        self.after = count;
+        self
+    }
+
+    /// Also emits up to `count` rejected records before and after each match.
+    pub fn context(self, count: usize) -> Self {
+        self.before(count).after(count)
+    }
+
+    /// Sets the text of the separator record emitted between groups of context.
+    ///
+    /// Records carry [`MatchContext::Separator`], but streams of lines only see
+    /// the text, so pick one that cannot be mistaken for a log line there.
+    pub fn group_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = Some(separator.into());
+        self
+    }
+
+    /// Emits groups of context without separator records between them.
+    pub fn no_group_separator(mut self) -> Self {
+        self.separator = None;
+        self
+    }
+
+    /// Returns true if the record passes the filter.
+    pub(crate) fn keeps(&self, record: &Record) -> bool {
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|include| include.set.is_match(&record.text));
+        let excluded = self
+            .exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.set.is_match(&record.text));
+
+        included && !excluded && self.predicates.iter().all(|predicate| predicate(record))
+    }
+}
+
+impl fmt::Debug for RecordFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordFilter")
+            .field("include", &self.include.as_ref().map(|set| &set.patterns))
+            .field("exclude", &self.exclude.as_ref().map(|set| &set.patterns))
+            .field("predicates", &self.predicates.len())
+            .field("before", &self.before)
+            .field("after", &self.after)
+            .field("separator", &self.separator)
+            .finish()
+    }
+}
+
+impl Default for RecordFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Holds recently rejected records so they can be emitted as context of a later match.
+#[derive(Debug)]
+pub(crate) struct ContextWindow {
+    before: usize,
+    after: usize,
+    separator: Option<String>,
+    recent: VecDeque<Record>,
+    after_remaining: usize,
+    emitted: bool,
+    skipped: bool,
+}
+
+impl ContextWindow {
+    /// Create a window for the filter, or `None` if it has no context lines.
+    pub(crate) fn new(filter: &RecordFilter) -> Option<Self> {
+        if filter.before == 0 && filter.after == 0 {
+            return None;
+        }
+        Some(Self {
+            before: filter.before,
+            after: filter.after,
+            separator: filter.separator.clone(),
+            recent: VecDeque::with_capacity(filter.before),
+            after_remaining: 0,
+            emitted: false,
+            skipped: false,
+        })
+    }
+
+    /// Add the next record, pushing anything to emit onto `output`, and
+    /// return how many records were dropped for good.
+    pub(crate) fn push(
+        &mut self,
+        mut record: Record,
+        matched: bool,
+        output: &mut Vec<Record>,
+    ) -> usize {
+        if matched {
+            if self.emitted
+                && self.skipped
+                && let Some(separator) = &self.separator
+            {
+                output.push(Record {
+                    context: Some(MatchContext::Separator),
+                    ..Record::new(separator.as_str())
+                });
+            }
+            output.extend(self.recent.drain(..).map(|mut record| {
+                record.context = Some(MatchContext::Before);
+                record
+            }));
+            record.context = Some(MatchContext::Match);
+            output.push(record);
+            self.after_remaining = self.after;
+            self.emitted = true;
+            self.skipped = false;
+            return 0;
+        }
+
+        if self.after_remaining > 0 {
+            self.after_remaining -= 1;
+            record.context = Some(MatchContext::After);
+            output.push(record);
+            return 0;
+        }
+
+        self.recent.push_back(record);
+        if self.recent.len() > self.before {
+            self.recent.pop_front();
+            self.skipped = true;
+            return 1;
+        }
+        0
+    }
+
+    /// Forget held records, such as after the file was truncated, and return how many there were.
+    pub(crate) fn clear(&mut self) -> usize {
+        let dropped = self.recent.len();
+        self.recent.clear();
+        self.after_remaining = 0;
+        self.skipped = self.emitted;
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    fn keeps(filter: &RecordFilter, text: &str) -> bool {
+        filter.keeps(&Record::new(text))
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let filter = RecordFilter::new()
+            .include(["ERROR"])
+            .unwrap()
+            .include(["WARN"])
+            .unwrap()
+            .exclude([r"GET /health\b"])
+            .unwrap();
+
+        assert!(keeps(&filter, "ERROR db down"));
+        assert!(keeps(&filter, "WARN slow"));
+        assert!(!keeps(&filter, "INFO started"));
+        assert!(!keeps(&filter, "WARN GET /health took 2s"));
+        assert!(keeps(&RecordFilter::new(), "anything"));
+    }
+
+    #[test]
+    fn test_predicates() {
+        let filter = RecordFilter::new()
+            .predicate(|record| record.level.is_some_and(|level| level >= Level::Warn))
+            .predicate(|record| !record.text.contains("ignored"));
+
+        let mut record = Record::new("disk full");
+        assert!(!filter.keeps(&record));
+        record.level = Some(Level::Error);
+        assert!(filter.keeps(&record));
+        record.text.push_str(" (ignored)");
+        assert!(!filter.keeps(&record));
+    }
+
+    #[test]
+    fn test_expression() {
+        let filter = RecordFilter::new()
+            .expression(r#"level >= warn && service == "api""#)
+            .unwrap();
+
+        let mut record = Record::new("request failed");
+        record.level = Some(Level::Error);
+        assert!(!filter.keeps(&record));
+        record.fields.insert("service", "api");
+        assert!(filter.keeps(&record));
+        assert!(RecordFilter::new().expression("level >=").is_err());
+    }
+
+    #[test]
+    fn test_context_window() {
+        let filter = RecordFilter::new().before(1).after(1);
+        let mut window = ContextWindow::new(&filter).unwrap();
+        let lines = [
+            "a", "b", "ERROR 1", "c", "d", "e", "ERROR 2", "ERROR 3", "f", "g",
+        ];
+
+        let mut output = Vec::new();
+        let mut dropped = 0;
+        for line in lines {
+            dropped += window.push(Record::new(line), line.starts_with("ERROR"), &mut output);
+        }
+
+        let emitted: Vec<(&str, MatchContext)> = output
+            .iter()
+            .map(|record| (record.text.as_str(), record.context.unwrap()))
+            .collect();
+        assert_eq!(
+            emitted,
+            vec![
+                ("b", MatchContext::Before),
+                ("ERROR 1", MatchContext::Match),
+                ("c", MatchContext::After),
+                ("--", MatchContext::Separator),
+                ("e", MatchContext::Before),
+                ("ERROR 2", MatchContext::Match),
+                ("ERROR 3", MatchContext::Match),
+                ("f", MatchContext::After),
+            ]
+        );
+        // "a" and "d" were dropped, "g" is still held
+        assert_eq!(dropped, 2);
+        assert_eq!(window.clear(), 1);
+
+        assert!(ContextWindow::new(&RecordFilter::new()).is_none());
+    }
+
+    #[test]
+    fn test_context_adjacent_groups_and_separator() {
+        let filter = RecordFilter::new().after(2).no_group_separator();
+        let mut window = ContextWindow::new(&filter).unwrap();
+        let mut output = Vec::new();
+        for (line, matched) in [
+            ("x", true),
+            ("y", false),
+            ("z", true),
+            ("1", false),
+            ("2", false),
+            ("3", false),
+            ("w", true),
+        ] {
+            window.push(Record::new(line), matched, &mut output);
+        }
+
+        let texts: Vec<&str> = output.iter().map(|record| record.text.as_str()).collect();
+        assert_eq!(texts, vec!["x", "y", "z", "1", "2", "w"]);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(RecordFilter::new().include(["("]).is_err());
+        assert!(RecordFilter::new().exclude(["["]).is_err());
+    }
+
+    #[test]
+    fn test_debug_lists_patterns() {
+        let filter = RecordFilter::new().exclude(["noise"]).unwrap();
+        let debug = format!("{:?}", filter);
+        assert!(debug.contains("noise"));
+        assert!(debug.contains("predicates: 0"));
+        assert!(debug.contains("before: 0"));
+    }
+}
@@ -0,0 +1,302 @@
+//! JSON Lines parsing on top of a log stream.
+
+use crate::error::Result;
+use crate::parser::Parser;
+use crate::record::{FieldValue, Fields};
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// How lines that fail to parse as JSON are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MalformedJson {
+    /// Pass the line through unchanged as [`JsonLine::Raw`].
+    #[default]
+    Raw,
+    /// Report the line and parse error as [`JsonLine::Invalid`].
+    Error,
+}
+
+/// A line that could not be parsed as JSON.
+#[derive(Debug)]
+pub struct JsonLineError {
+    /// The original line.
+    pub line: String,
+    /// Why parsing failed.
+    pub error: serde_json::Error,
+}
+
+impl std::fmt::Display for JsonLineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid JSON line: {}", self.error)
+    }
+}
+
+impl std::error::Error for JsonLineError {}
+
+/// One record of a JSON Lines stream.
+#[derive(Debug)]
+pub enum JsonLine<T = serde_json::Value> {
+    /// The line was valid JSON for the target type.
+    Parsed(T),
+    /// The line was not valid JSON and is passed through as text.
+    Raw(String),
+    /// The line was not valid JSON.
+    Invalid(JsonLineError),
+}
+
+impl<T> JsonLine<T> {
+    /// Returns the parsed value, if the line was valid.
+    pub fn parsed(self) -> Option<T> {
+        match self {
+            JsonLine::Parsed(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A stream adapter that parses every line of a log stream as JSON.
+///
+/// Lines are deserialized into `T`, which defaults to [`serde_json::Value`].
+/// Malformed lines never end the stream, they are reported per line according
+/// to the [`MalformedJson`] policy.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use log_reader::{JsonLine, JsonLines, watch_log};
+/// use tokio_stream::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut stream = JsonLines::<_>::new(watch_log("app.jsonl", None).await?);
+///
+///     while let Some(records) = stream.next().await {
+///         for record in records? {
+///             if let JsonLine::Parsed(value) = record {
+///                 println!("level: {}", value["level"]);
+///             }
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct JsonLines<S, T = serde_json::Value> {
+    inner: S,
+    malformed: MalformedJson,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<S, T> JsonLines<S, T> {
+    /// Wraps a stream of lines.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            malformed: MalformedJson::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets how lines that are not valid JSON are reported.
+    pub fn malformed(mut self, malformed: MalformedJson) -> Self {
+        self.malformed = malformed;
+        self
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, T> Stream for JsonLines<S, T>
+where
+    S: Stream<Item = Result<Vec<String>>> + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<Vec<JsonLine<T>>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let malformed = self.malformed;
+        Pin::new(&mut self.inner).poll_next(cx).map(|item| {
+            item.map(|lines| {
+                lines.map(|lines| {
+                    lines
+                        .into_iter()
+                        .map(|line| parse_json_line(line, malformed))
+                        .collect()
+                })
+            })
+        })
+    }
+}
+
+/// Parse a single line, reporting failures according to the policy
+fn parse_json_line<T: DeserializeOwned>(line: String, malformed: MalformedJson) -> JsonLine<T> {
+    match serde_json::from_str(&line) {
+        Ok(value) => JsonLine::Parsed(value),
+        Err(error) => match malformed {
+            MalformedJson::Raw => JsonLine::Raw(line),
+            MalformedJson::Error => JsonLine::Invalid(JsonLineError { line, error }),
+        },
+    }
+}
+
+/// Parses JSON object lines into record fields for use with
+/// [`StreamConfig::parser`](crate::StreamConfig::parser).
+///
+/// Nested objects become [`FieldValue::Map`] and arrays [`FieldValue::List`].
+/// Lines that are not JSON objects are left without fields.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonParser;
+
+impl JsonParser {
+    /// Creates a JSON parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Parser for JsonParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        match serde_json::from_str(line).ok()? {
+            serde_json::Value::Object(object) => Some(object_to_fields(object)),
+            _ => None,
+        }
+    }
+}
+
+/// Convert a JSON object into fields
+fn object_to_fields(object: serde_json::Map<String, serde_json::Value>) -> Fields {
+    object
+        .into_iter()
+        .map(|(key, value)| (key, json_to_field_value(value)))
+        .collect()
+}
+
+/// Convert a JSON value into the equivalent field value
+fn json_to_field_value(value: serde_json::Value) -> FieldValue {
+    match value {
+        serde_json::Value::Null => FieldValue::Null,
+        serde_json::Value::Bool(value) => FieldValue::Bool(value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(value) => FieldValue::Int(value),
+            None => FieldValue::Float(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(value) => FieldValue::String(value),
+        serde_json::Value::Array(values) => {
+            FieldValue::List(values.into_iter().map(json_to_field_value).collect())
+        }
+        serde_json::Value::Object(object) => FieldValue::Map(object_to_fields(object)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tokio_stream::StreamExt;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Entry {
+        level: String,
+        msg: String,
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_json_line_value() {
+        let line = r#"{"level":"info","port":8080}"#.to_string();
+        let parsed: JsonLine = parse_json_line(line, MalformedJson::Raw);
+        let value = parsed.parsed().unwrap();
+        assert_eq!(value["level"], "info");
+        assert_eq!(value["port"], 8080);
+    }
+
+    #[test]
+    fn test_parse_json_line_malformed_raw() {
+        let parsed: JsonLine = parse_json_line("not json".to_string(), MalformedJson::Raw);
+        match parsed {
+            JsonLine::Raw(line) => assert_eq!(line, "not json"),
+            other => panic!("Expected raw line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_line_malformed_error() {
+        let parsed: JsonLine = parse_json_line("{broken".to_string(), MalformedJson::Error);
+        match parsed {
+            JsonLine::Invalid(error) => {
+                assert_eq!(error.line, "{broken");
+                assert!(error.to_string().starts_with("Invalid JSON line"));
+            }
+            other => panic!("Expected invalid line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_line_user_type() {
+        let line = r#"{"level":"warn","msg":"disk low","extra":1}"#.to_string();
+        let parsed: JsonLine<Entry> = parse_json_line(line, MalformedJson::Raw);
+        assert_eq!(
+            parsed.parsed(),
+            Some(Entry {
+                level: "warn".to_string(),
+                msg: "disk low".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_parser_fields() {
+        let line = r#"{"level":"info","port":8080,"ratio":0.5,"ok":true,"tags":["a"],"req":{"id":1},"none":null}"#;
+        let fields = JsonParser::new().parse(line).unwrap();
+
+        assert_eq!(fields.get_str("level"), Some("info"));
+        assert_eq!(fields.get("port"), Some(&FieldValue::Int(8080)));
+        assert_eq!(fields.get("ratio"), Some(&FieldValue::Float(0.5)));
+        assert_eq!(fields.get("ok"), Some(&FieldValue::Bool(true)));
+        assert_eq!(
+            fields.get("tags"),
+            Some(&FieldValue::List(vec![FieldValue::from("a")]))
+        );
+        match fields.get("req") {
+            Some(FieldValue::Map(req)) => assert_eq!(req.get("id"), Some(&FieldValue::Int(1))),
+            other => panic!("Expected nested map, got {:?}", other),
+        }
+        assert_eq!(fields.get("none"), Some(&FieldValue::Null));
+    }
+
+    #[test]
+    fn test_json_parser_rejects_non_objects() {
+        let parser = JsonParser::new();
+        assert!(parser.parse("[1, 2]").is_none());
+        assert!(parser.parse("plain text").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_json_lines_stream_keeps_going_after_malformed_line() {
+        let batches: Vec<Result<Vec<String>>> = vec![
+            Ok(lines(&[r#"{"level":"info","msg":"a"}"#, "garbage"])),
+            Ok(lines(&[r#"{"level":"error","msg":"b"}"#])),
+        ];
+        let mut stream =
+            JsonLines::<_, Entry>::new(tokio_stream::iter(batches)).malformed(MalformedJson::Error);
+
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(matches!(first[0], JsonLine::Parsed(ref entry) if entry.msg == "a"));
+        assert!(matches!(first[1], JsonLine::Invalid(_)));
+
+        let second = stream.next().await.unwrap().unwrap();
+        assert!(matches!(second[0], JsonLine::Parsed(ref entry) if entry.level == "error"));
+
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,323 @@
+//! Classification of records by log level.
+
+use crate::error::Error;
+use crate::record::{FieldValue, Fields, Record};
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Syslog or kernel priority at the start of a line: `<34>`.
+static PRIORITY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<(\d{1,3})>").unwrap());
+
+/// glog style prefix: `E1017 12:00:00.000000 ...`.
+static GLOG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([IWEF])\d{4} ").unwrap());
+
+/// A level written as a key/value pair: `level=warn`, `"level":"warn"` or `severity: ERROR`.
+static KEY_VALUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(?:level|lvl|severity|loglevel)["']?\s*[=:]\s*["']?([a-z]+)"#).unwrap()
+});
+
+/// A level token: any case inside brackets (`[error]`), otherwise upper case (`WARN`).
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        \[\s*(?i:(trace|debug|info|notice|warn|warning|error|err|fatal|crit|critical|alert|emerg|severe|panic))\s*\]
+        | \b(TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|FATAL|CRITICAL|SEVERE|PANIC)\b
+        ",
+    )
+    .unwrap()
+});
+
+/// The severity of a record, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Fine grained tracing.
+    Trace,
+    /// Debugging information.
+    Debug,
+    /// Normal operation, including syslog `notice`.
+    Info,
+    /// Something unexpected that was handled.
+    Warn,
+    /// A failed operation.
+    Error,
+    /// A failure the program cannot recover from, including syslog `crit`, `alert` and `emerg`.
+    Fatal,
+}
+
+impl Level {
+    /// All levels from least to most severe.
+    pub const ALL: [Level; 6] = [
+        Level::Trace,
+        Level::Debug,
+        Level::Info,
+        Level::Warn,
+        Level::Error,
+        Level::Fatal,
+    ];
+
+    /// Returns the level of a syslog severity code (0 to 7).
+    pub fn from_syslog_severity(severity: u8) -> Option<Level> {
+        match severity {
+            0..=2 => Some(Level::Fatal),
+            3 => Some(Level::Error),
+            4 => Some(Level::Warn),
+            5 | 6 => Some(Level::Info),
+            7 => Some(Level::Debug),
+            _ => None,
+        }
+    }
+
+    /// Returns the level of a name such as `warn`, `WARNING` or `err`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "trace" | "trc" | "finest" | "finer" => Some(Level::Trace),
+            "debug" | "dbg" | "fine" | "verbose" => Some(Level::Debug),
+            "info" | "inf" | "information" | "informational" | "notice" => Some(Level::Info),
+            "warn" | "wrn" | "warning" => Some(Level::Warn),
+            "error" | "err" | "eror" | "severe" => Some(Level::Error),
+            "fatal" | "ftl" | "crit" | "critical" | "alert" | "emerg" | "emergency" | "panic" => {
+                Some(Level::Fatal)
+            }
+            _ => None,
+        }
+    }
+
+    /// Level of a numeric field: a syslog severity or a bunyan/pino level
+    fn from_number(number: i64) -> Option<Level> {
+        match number {
+            0..=7 => Level::from_syslog_severity(number as u8),
+            10 => Some(Level::Trace),
+            20 => Some(Level::Debug),
+            30 => Some(Level::Info),
+            40 => Some(Level::Warn),
+            50 => Some(Level::Error),
+            60 => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Returns the upper case name of the level.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+            Level::Fatal => "FATAL",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Level::from_name(name).ok_or_else(|| Error::InvalidFormat {
+            message: format!("unknown log level '{}'", name),
+        })
+    }
+}
+
+/// Settings for classifying records by level and dropping the less severe ones.
+///
+/// The level is taken from the first of the [`fields`](Self::fields) set by
+/// the parser that holds a known level name or number. Otherwise it is
+/// guessed from the text, in this order: a syslog `<3>` priority, a glog
+/// `E1017` prefix, a `level=warn` style pair, and finally the first level
+/// token such as `[error]` or `WARN`.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{Level, LevelConfig, StreamConfig};
+///
+/// let config = StreamConfig::new().levels(LevelConfig::new().min_level(Level::Warn));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelConfig {
+    pub(crate) fields: Vec<String>,
+    pub(crate) min_level: Option<Level>,
+    pub(crate) keep_unknown: bool,
+}
+
+impl LevelConfig {
+    /// Fields checked for the level by default.
+    pub const DEFAULT_FIELDS: &[&str] = &["level", "severity", "lvl", "loglevel", "levelname"];
+
+    /// Creates a configuration that classifies records without dropping any.
+    pub fn new() -> Self {
+        Self {
+            fields: Self::DEFAULT_FIELDS.iter().map(|s| s.to_string()).collect(),
+            min_level: None,
+            keep_unknown: true,
+        }
+    }
+
+    /// Sets the fields checked for the level, in order.
+    pub fn fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Drops records less severe than `level`.
+    pub fn min_level(mut self, level: Level) -> Self {
+        self.min_level = Some(level);
+        self
+    }
+
+    /// Sets whether records without a recognisable level are kept when a
+    /// minimum level is set (the default) or dropped.
+    pub fn keep_unknown(mut self, keep: bool) -> Self {
+        self.keep_unknown = keep;
+        self
+    }
+
+    /// Find the level of a record from its fields or text.
+    pub(crate) fn detect(&self, record: &Record) -> Option<Level> {
+        self.find_in_fields(&record.fields)
+            .or_else(|| detect_level(&record.text))
+    }
+
+    /// Returns true if a record with this level passes the minimum level.
+    pub(crate) fn keeps(&self, level: Option<Level>) -> bool {
+        match (self.min_level, level) {
+            (None, _) => true,
+            (Some(min_level), Some(level)) => level >= min_level,
+            (Some(_), None) => self.keep_unknown,
+        }
+    }
+
+    /// Use the first configured field holding a known level
+    fn find_in_fields(&self, fields: &Fields) -> Option<Level> {
+        self.fields.iter().find_map(|name| match fields.get(name)? {
+            FieldValue::String(name) => Level::from_name(name),
+            FieldValue::Int(number) => Level::from_number(*number),
+            _ => None,
+        })
+    }
+}
+
+impl Default for LevelConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guess the level of a line from common conventions
+fn detect_level(text: &str) -> Option<Level> {
+    if let Some(captures) = PRIORITY.captures(text) {
+        let priority: u32 = captures[1].parse().ok()?;
+        return Level::from_syslog_severity((priority % 8) as u8);
+    }
+    if let Some(captures) = GLOG.captures(text) {
+        return match &captures[1] {
+            "I" => Some(Level::Info),
+            "W" => Some(Level::Warn),
+            "E" => Some(Level::Error),
+            _ => Some(Level::Fatal),
+        };
+    }
+    if let Some(level) = KEY_VALUE
+        .captures_iter(text)
+        .find_map(|captures| Level::from_name(&captures[1]))
+    {
+        return Some(level);
+    }
+    TOKEN.captures(text).and_then(|captures| {
+        let token = captures.get(1).or_else(|| captures.get(2))?;
+        Level::from_name(token.as_str())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_names_and_order() {
+        assert_eq!(Level::from_name("WARNING"), Some(Level::Warn));
+        assert_eq!(Level::from_name("err"), Some(Level::Error));
+        assert_eq!(Level::from_name("notice"), Some(Level::Info));
+        assert_eq!(Level::from_name("crit"), Some(Level::Fatal));
+        assert_eq!(Level::from_name("loud"), None);
+        assert_eq!("debug".parse::<Level>().unwrap(), Level::Debug);
+        assert!("loud".parse::<Level>().is_err());
+
+        assert!(Level::Trace < Level::Debug && Level::Error < Level::Fatal);
+        assert_eq!(Level::Warn.to_string(), "WARN");
+    }
+
+    #[test]
+    fn test_detect_level_from_text() {
+        let cases = [
+            ("<3>kernel: oops", Some(Level::Error)),
+            (
+                "<38>Oct 17 12:00:00 host sshd[1]: accepted",
+                Some(Level::Info),
+            ),
+            (
+                "E1017 12:00:00.000000  1234 main.cc:10] failed",
+                Some(Level::Error),
+            ),
+            (
+                "W1017 12:00:00.000000  1234 main.cc:10] slow",
+                Some(Level::Warn),
+            ),
+            ("time=12:00 level=warn msg=\"disk full\"", Some(Level::Warn)),
+            (r#"{"level":"debug","msg":"tick"}"#, Some(Level::Debug)),
+            (
+                "2026-10-17 12:00:00 [error] 42#0: upstream timed out",
+                Some(Level::Error),
+            ),
+            ("2026-10-17 12:00:00 INFO Server started", Some(Level::Info)),
+            (
+                "12:00:00 [main] WARN  c.e.App - slow request",
+                Some(Level::Warn),
+            ),
+            // Lower case words are too common to count outside brackets
+            ("no error here", None),
+            ("plain line", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(detect_level(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_detect_level_from_fields() {
+        let config = LevelConfig::new();
+        let mut record = Record::new("ERROR in text");
+
+        record.fields.insert("severity", "warning");
+        assert_eq!(config.detect(&record), Some(Level::Warn));
+
+        record.fields.insert("level", 30);
+        assert_eq!(config.detect(&record), Some(Level::Info));
+
+        // Unknown values fall back to the text
+        let mut record = Record::new("ERROR in text");
+        record.fields.insert("level", "custom");
+        assert_eq!(config.detect(&record), Some(Level::Error));
+    }
+
+    #[test]
+    fn test_min_level() {
+        let config = LevelConfig::new().min_level(Level::Warn);
+        assert!(config.keeps(Some(Level::Error)));
+        assert!(config.keeps(Some(Level::Warn)));
+        assert!(!config.keeps(Some(Level::Info)));
+        assert!(config.keeps(None));
+        assert!(!config.keep_unknown(false).keeps(None));
+        assert!(LevelConfig::new().keeps(Some(Level::Trace)));
+    }
+}
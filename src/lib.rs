@@ -29,17 +29,71 @@
 //! ```
 
 // Internal modules - not part of public API
+mod ansi;
+mod audit;
+mod config;
+mod container;
+mod control;
+mod dedup;
+mod delimited;
+mod delimiter;
 mod error;
+mod expr;
+mod filter;
+#[cfg(feature = "json")]
+mod json;
+mod level;
+mod multiline;
+mod parser;
+mod processor;
 mod reader;
+mod record;
+mod redact;
+mod sampling;
+#[cfg(feature = "scripting")]
+mod script;
+mod stacktrace;
+mod stats;
 mod stream;
+mod timestamp;
 mod watcher;
 
 #[cfg(test)]
 mod test_helpers;
 
 // Public API exports
+pub use ansi::AnsiMode;
+pub use audit::AuditConfig;
+pub use config::{EmptyRecordPolicy, StreamConfig};
+pub use container::ContainerFormat;
+pub use control::StreamControl;
+pub use dedup::DedupConfig;
+pub use delimited::{DelimitedFormat, Header};
+pub use delimiter::Delimiter;
 pub use error::{Error, Result};
-pub use stream::LogStream;
+pub use expr::FilterExpr;
+pub use filter::{MatchContext, RecordFilter};
+#[cfg(feature = "json")]
+pub use json::{JsonLine, JsonLineError, JsonLines, JsonParser, MalformedJson};
+pub use level::{Level, LevelConfig};
+pub use multiline::{MultilineConfig, MultilineRule};
+pub use parser::{
+    AccessLogParser, AuditParser, COMBINED_LOG_FORMAT, COMMON_LOG_FORMAT, FieldType, GrokParser,
+    GrokPatterns, LogfmtParser, Parser, RegexParser, SyslogFormat, SyslogParser,
+};
+pub use processor::{AsyncLineProcessor, LineProcessor, Pipeline};
+pub use record::{FieldValue, Fields, Record};
+pub use redact::{Detector, RedactionConfig, RedactionMode};
+pub use sampling::{SampleMode, SamplingConfig};
+#[cfg(feature = "scripting")]
+pub use script::ScriptProcessor;
+pub use stacktrace::StackTraceDetector;
+pub use stats::StreamStats;
+pub use stream::{CloseSummary, LogStream};
+pub use timestamp::TimestampConfig;
+
+/// Timezones for [`TimestampConfig::timezone`], from the `chrono-tz` crate.
+pub use chrono_tz::Tz;
 
 use std::path::Path;
 use tokio_stream::Stream;
@@ -77,11 +131,53 @@ pub async fn watch_log<P: AsRef<Path>>(
     LogStream::new(path, separator).await
 }
 
+/// Creates a stream that watches a file for new content using a custom configuration.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use log_reader::{EmptyRecordPolicy, StreamConfig, watch_log_with_config};
+/// use tokio_stream::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = StreamConfig::new()
+///         .empty_records(EmptyRecordPolicy::KeepAll)
+///         .trim_carriage_return(true);
+///     let mut stream = watch_log_with_config("app.log", config).await?;
+///
+///     while let Some(lines) = stream.next().await {
+///         for line in lines? {
+///             println!("New line: {}", line);
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn watch_log_with_config<P: AsRef<Path>>(
+    path: P,
+    config: StreamConfig,
+) -> Result<impl Stream<Item = Result<Vec<String>>>> {
+    LogStream::with_config(path, config).await
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+
     #[tokio::test]
     async fn test_basic_functionality() {
-        // Placeholder test - will be implemented with proper fixtures
-        assert!(true);
+        let stream = watch_log("fixtures/simple_append.log", None).await.unwrap();
+        tokio::pin!(stream);
+
+        let first = tokio::time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .expect("Should yield existing content")
+            .expect("Stream should not end")
+            .expect("Read should succeed");
+        assert_eq!(first.len(), 10);
     }
 }
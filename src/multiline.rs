@@ -0,0 +1,460 @@
+//! Aggregation of several physical lines into one logical record.
+
+use crate::error::Result;
+use crate::record::Record;
+use crate::stacktrace::{StackTraceDetector, StackTraceMatcher};
+use regex::Regex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Decides which lines belong to the same record.
+#[derive(Debug, Clone)]
+pub enum MultilineRule {
+    /// Lines starting with a space or tab continue the previous record.
+    ContinuationWhitespace,
+    /// Lines matching the regex start a new record, all others continue the current one.
+    Start(Regex),
+    /// Lines matching the regex end the current record.
+    End(Regex),
+    /// Exception and panic traces recognised by the selected detectors are kept
+    /// with the line that started them, every other line is its own record.
+    StackTraces(Vec<StackTraceDetector>),
+}
+
+/// Configuration for joining multiline records such as stack traces.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{MultilineConfig, StreamConfig};
+/// use std::time::Duration;
+///
+/// let multiline = MultilineConfig::start_pattern(r"^\d{4}-\d{2}-\d{2}")
+///     .unwrap()
+///     .max_lines(200)
+///     .flush_timeout(Duration::from_millis(500));
+/// let config = StreamConfig::new().multiline(multiline);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultilineConfig {
+    pub(crate) rule: MultilineRule,
+    pub(crate) max_lines: usize,
+    pub(crate) max_bytes: usize,
+    pub(crate) flush_timeout: Duration,
+}
+
+impl MultilineConfig {
+    /// Default maximum number of lines in one record.
+    pub const DEFAULT_MAX_LINES: usize = 500;
+    /// Default maximum size of one record in bytes.
+    pub const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+    /// Default time an incomplete record is held before it is emitted.
+    pub const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Creates a configuration using the given rule and default limits.
+    pub fn new(rule: MultilineRule) -> Self {
+        Self {
+            rule,
+            max_lines: Self::DEFAULT_MAX_LINES,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            flush_timeout: Self::DEFAULT_FLUSH_TIMEOUT,
+        }
+    }
+
+    /// Joins lines that start with whitespace onto the previous record.
+    pub fn continuation_whitespace() -> Self {
+        Self::new(MultilineRule::ContinuationWhitespace)
+    }
+
+    /// Starts a new record at every line matching `pattern`.
+    pub fn start_pattern(pattern: &str) -> Result<Self> {
+        Ok(Self::new(MultilineRule::Start(Regex::new(pattern)?)))
+    }
+
+    /// Ends the current record at every line matching `pattern`.
+    pub fn end_pattern(pattern: &str) -> Result<Self> {
+        Ok(Self::new(MultilineRule::End(Regex::new(pattern)?)))
+    }
+
+    /// Keeps stack traces recognised by the given detectors together.
+    ///
+    /// Use [`StackTraceDetector::ALL`] to enable every built-in detector.
+    pub fn stack_traces(detectors: impl IntoIterator<Item = StackTraceDetector>) -> Self {
+        Self::new(MultilineRule::StackTraces(detectors.into_iter().collect()))
+    }
+
+    /// Sets the maximum number of lines joined into one record.
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    /// Sets the maximum size in bytes of one record. The record is emitted
+    /// before a line that would take it over the limit, so only a single line
+    /// longer than the limit makes a larger record.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// Sets how long an incomplete record waits for more lines before it is emitted.
+    pub fn flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+}
+
+/// Joins lines into records according to a [`MultilineConfig`].
+#[derive(Debug)]
+pub(crate) struct MultilineAggregator {
+    config: MultilineConfig,
+    traces: Option<StackTraceMatcher>,
+    pending: Option<Record>,
+    pending_lines: usize,
+    /// Blank lines inside a stack trace, held until the next line shows whether it continues.
+    blank_lines: Vec<Record>,
+    last_update: Instant,
+}
+
+impl MultilineAggregator {
+    pub(crate) fn new(config: MultilineConfig) -> Self {
+        let traces = match &config.rule {
+            MultilineRule::StackTraces(detectors) => {
+                Some(StackTraceMatcher::new(detectors.clone()))
+            }
+            _ => None,
+        };
+
+        Self {
+            config,
+            traces,
+            pending: None,
+            pending_lines: 0,
+            blank_lines: Vec::new(),
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Adds lines and returns the records they completed.
+    ///
+    /// A joined record keeps the metadata of its first line.
+    pub(crate) fn push_all(&mut self, lines: Vec<Record>) -> Vec<Record> {
+        let mut records = Vec::new();
+        for line in lines {
+            self.push(line, &mut records);
+        }
+        self.last_update = Instant::now();
+        records
+    }
+
+    /// Emits the record being assembled, followed by any held back blank lines.
+    pub(crate) fn flush(&mut self) -> Vec<Record> {
+        self.pending_lines = 0;
+        self.pending
+            .take()
+            .into_iter()
+            .chain(self.blank_lines.drain(..))
+            .collect()
+    }
+
+    /// Returns when the pending record should be emitted if no more lines arrive.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        (self.pending.is_some() || !self.blank_lines.is_empty())
+            .then(|| self.last_update + self.config.flush_timeout)
+    }
+
+    fn push(&mut self, line: Record, records: &mut Vec<Record>) {
+        if let Some(traces) = &self.traces
+            && traces.in_trace()
+            && line.text.trim().is_empty()
+        {
+            self.blank_lines.push(line);
+            return;
+        }
+
+        let text = &line.text;
+        let starts_record = match &self.config.rule {
+            MultilineRule::ContinuationWhitespace => !text.starts_with([' ', '\t']),
+            MultilineRule::Start(regex) => regex.is_match(text),
+            MultilineRule::End(_) => false,
+            MultilineRule::StackTraces(_) => self
+                .traces
+                .as_mut()
+                .is_none_or(|traces| !traces.continues(text)),
+        };
+        let ends_record = match &self.config.rule {
+            MultilineRule::End(regex) => regex.is_match(text),
+            _ => false,
+        };
+
+        if starts_record {
+            records.extend(self.flush());
+        }
+
+        // The trace carries on, so the blank lines before this one belong to it
+        for blank in std::mem::take(&mut self.blank_lines) {
+            self.append(blank, records);
+        }
+        self.append(line, records);
+
+        let pending_bytes = self
+            .pending
+            .as_ref()
+            .map_or(0, |pending| pending.text.len());
+        if ends_record
+            || self.pending_lines >= self.config.max_lines
+            || pending_bytes >= self.config.max_bytes
+        {
+            records.extend(self.flush());
+        }
+    }
+
+    fn append(&mut self, line: Record, records: &mut Vec<Record>) {
+        if let Some(pending) = &self.pending
+            && pending.text.len() + 1 + line.text.len() > self.config.max_bytes
+        {
+            records.extend(self.flush());
+        }
+
+        match &mut self.pending {
+            Some(pending) => {
+                pending.text.push('\n');
+                pending.text.push_str(&line.text);
+            }
+            None => self.pending = Some(line),
+        }
+        self.pending_lines += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<Record> {
+        lines.iter().map(|line| Record::new(*line)).collect()
+    }
+
+    fn push_lines(aggregator: &mut MultilineAggregator, lines: Vec<Record>) -> Vec<String> {
+        aggregator
+            .push_all(lines)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn flush_text(aggregator: &mut MultilineAggregator) -> Option<String> {
+        aggregator.flush().into_iter().map(String::from).next()
+    }
+
+    #[test]
+    fn test_continuation_whitespace() {
+        let mut aggregator = MultilineAggregator::new(MultilineConfig::continuation_whitespace());
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&[
+                "Exception in thread \"main\"",
+                "    at com.example.Main.run(Main.java:10)",
+                "\tat com.example.Main.main(Main.java:5)",
+                "next record",
+            ]),
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "Exception in thread \"main\"\n    at com.example.Main.run(Main.java:10)\n\tat com.example.Main.main(Main.java:5)"
+            ]
+        );
+        assert_eq!(flush_text(&mut aggregator), Some("next record".to_string()));
+        assert_eq!(flush_text(&mut aggregator), None);
+    }
+
+    #[test]
+    fn test_start_pattern() {
+        let config = MultilineConfig::start_pattern(r"^\d{4}-").unwrap();
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&[
+                "2024-01-01 ERROR failed",
+                "Traceback (most recent call last):",
+                "ValueError: bad",
+                "2024-01-01 INFO ok",
+            ]),
+        );
+
+        assert_eq!(
+            records,
+            vec!["2024-01-01 ERROR failed\nTraceback (most recent call last):\nValueError: bad"]
+        );
+        assert_eq!(
+            flush_text(&mut aggregator),
+            Some("2024-01-01 INFO ok".to_string())
+        );
+    }
+
+    #[test]
+    fn test_start_pattern_leading_lines() {
+        let config = MultilineConfig::start_pattern(r"^START").unwrap();
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(&mut aggregator, lines(&["orphan", "START a", "more"]));
+
+        assert_eq!(records, vec!["orphan"]);
+        assert_eq!(
+            flush_text(&mut aggregator),
+            Some("START a\nmore".to_string())
+        );
+    }
+
+    #[test]
+    fn test_end_pattern() {
+        let config = MultilineConfig::end_pattern(r";$").unwrap();
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&["SELECT *", "FROM t;", "UPDATE t", "SET a = 1"]),
+        );
+
+        assert_eq!(records, vec!["SELECT *\nFROM t;"]);
+        assert_eq!(
+            flush_text(&mut aggregator),
+            Some("UPDATE t\nSET a = 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_max_lines_cap() {
+        let config = MultilineConfig::continuation_whitespace().max_lines(2);
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(&mut aggregator, lines(&["head", " one", " two", " three"]));
+
+        assert_eq!(records, vec!["head\n one", " two\n three"]);
+        assert_eq!(flush_text(&mut aggregator), None);
+    }
+
+    #[test]
+    fn test_max_bytes_cap() {
+        let config = MultilineConfig::continuation_whitespace().max_bytes(10);
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&["12345", " 6789", " abc", "0123456789ab", " d"]),
+        );
+
+        assert_eq!(records, vec!["12345", " 6789\n abc", "0123456789ab"]);
+        assert!(records[..2].iter().all(|record| record.len() <= 10));
+        assert_eq!(flush_text(&mut aggregator), Some(" d".to_string()));
+    }
+
+    #[test]
+    fn test_records_span_pushes() {
+        let mut aggregator = MultilineAggregator::new(MultilineConfig::continuation_whitespace());
+
+        assert!(push_lines(&mut aggregator, lines(&["head", " one"])).is_empty());
+        let records = push_lines(&mut aggregator, lines(&[" two", "next"]));
+
+        assert_eq!(records, vec!["head\n one\n two"]);
+    }
+
+    #[tokio::test]
+    async fn test_flush_deadline() {
+        let timeout = Duration::from_millis(50);
+        let config = MultilineConfig::continuation_whitespace().flush_timeout(timeout);
+        let mut aggregator = MultilineAggregator::new(config);
+        assert!(aggregator.flush_deadline().is_none());
+
+        let before = Instant::now();
+        push_lines(&mut aggregator, lines(&["head"]));
+        let deadline = aggregator.flush_deadline().unwrap();
+        assert!(deadline >= before + timeout);
+
+        flush_text(&mut aggregator);
+        assert!(aggregator.flush_deadline().is_none());
+    }
+
+    #[test]
+    fn test_stack_traces() {
+        let config = MultilineConfig::stack_traces(StackTraceDetector::ALL);
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&[
+                "ERROR request failed",
+                "java.lang.RuntimeException: boom",
+                "\tat com.example.Main.main(Main.java:5)",
+                "INFO recovered",
+                "thread 'main' panicked at src/main.rs:2:5:",
+                "explicit panic",
+                "INFO exiting",
+            ]),
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "ERROR request failed\njava.lang.RuntimeException: boom\n\tat com.example.Main.main(Main.java:5)",
+                "INFO recovered",
+                "thread 'main' panicked at src/main.rs:2:5:\nexplicit panic",
+            ]
+        );
+        assert_eq!(
+            flush_text(&mut aggregator),
+            Some("INFO exiting".to_string())
+        );
+    }
+
+    #[test]
+    fn test_stack_trace_blank_lines() {
+        let config = MultilineConfig::stack_traces(StackTraceDetector::ALL);
+        let mut aggregator = MultilineAggregator::new(config);
+
+        let records = push_lines(
+            &mut aggregator,
+            lines(&[
+                "panic: boom",
+                "",
+                "goroutine 1 [running]:",
+                "main.main()",
+                "",
+                "INFO restarted",
+                "thread 'main' panicked at src/main.rs:2:5:",
+                "explicit panic",
+                "",
+            ]),
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "panic: boom\n\ngoroutine 1 [running]:\nmain.main()",
+                "",
+                "INFO restarted",
+            ]
+        );
+
+        // Trailing blank lines are not attached to the trace
+        let flushed: Vec<String> = aggregator.flush().into_iter().map(String::from).collect();
+        assert_eq!(
+            flushed,
+            vec![
+                "thread 'main' panicked at src/main.rs:2:5:\nexplicit panic",
+                ""
+            ]
+        );
+        assert!(aggregator.flush_deadline().is_none());
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(MultilineConfig::start_pattern("(").is_err());
+        assert!(MultilineConfig::end_pattern("[").is_err());
+    }
+}
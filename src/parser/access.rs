@@ -0,0 +1,298 @@
+//! Parser for Apache/Nginx access logs in the common, combined or a custom nginx `log_format`.
+
+use super::Parser;
+use crate::error::{Error, Result};
+use crate::record::{FieldValue, Fields};
+use chrono::DateTime;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// The Common Log Format written by Apache and nginx.
+pub const COMMON_LOG_FORMAT: &str =
+    r#"$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent"#;
+
+/// The nginx `combined` format, also Apache's combined format.
+pub const COMBINED_LOG_FORMAT: &str = r#"$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent""#;
+
+/// The `$time_local` format, e.g. `10/Oct/2000:13:55:36 -0700`.
+const TIME_LOCAL_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+static COMMON: LazyLock<AccessLogParser> =
+    LazyLock::new(|| AccessLogParser::nginx_format(COMMON_LOG_FORMAT).unwrap());
+static COMBINED: LazyLock<AccessLogParser> =
+    LazyLock::new(|| AccessLogParser::nginx_format(COMBINED_LOG_FORMAT).unwrap());
+
+/// Parses access log lines described by an nginx `log_format` string.
+///
+/// Well known variables are stored under friendlier names with typed values:
+/// `remote_addr`, `remote_user`, `time` as a timestamp (which also sets
+/// [`Record::timestamp`](crate::Record::timestamp)), `method`, `path` and
+/// `protocol` (split from `$request`), `status` and `bytes` as integers,
+/// `referer`, `user_agent`, and `request_time` as a float. Other variables keep
+/// their name without the `$`. Values logged as `-` are left out, and values
+/// that do not read as their type are kept as strings.
+#[derive(Debug, Clone)]
+pub struct AccessLogParser {
+    pattern: Regex,
+    variables: Vec<String>,
+}
+
+impl AccessLogParser {
+    /// Creates a parser for the Common Log Format.
+    pub fn common() -> Self {
+        COMMON.clone()
+    }
+
+    /// Creates a parser for the combined log format.
+    pub fn combined() -> Self {
+        COMBINED.clone()
+    }
+
+    /// Creates a parser from an nginx `log_format` string such as
+    /// `$remote_addr [$time_local] "$request" $status $request_time`.
+    pub fn nginx_format(format: &str) -> Result<Self> {
+        let mut pattern = String::from("^");
+        let mut variables = Vec::new();
+        let mut rest = format;
+
+        while let Some(start) = rest.find('$') {
+            pattern.push_str(&regex::escape(&rest[..start]));
+            let (name, after) =
+                split_variable(&rest[start + 1..]).ok_or_else(|| Error::InvalidFormat {
+                    message: format!("bad variable at `{}` in `{}`", &rest[start..], format),
+                })?;
+            pattern.push_str(variable_pattern(name));
+            variables.push(name.to_string());
+            rest = after;
+        }
+        pattern.push_str(&regex::escape(rest));
+        pattern.push_str(r"\s*$");
+
+        if variables.is_empty() {
+            return Err(Error::InvalidFormat {
+                message: format!("no variables in `{}`", format),
+            });
+        }
+
+        Ok(Self {
+            pattern: Regex::new(&pattern)?,
+            variables,
+        })
+    }
+}
+
+impl Parser for AccessLogParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        let captures = self.pattern.captures(line)?;
+        let mut fields = Fields::new();
+
+        for (variable, value) in self.variables.iter().zip(captures.iter().skip(1)) {
+            let value = value.map_or("", |m| m.as_str());
+            if value.is_empty() || value == "-" {
+                continue;
+            }
+            insert_variable(&mut fields, variable, value);
+        }
+
+        Some(fields)
+    }
+}
+
+/// Split a variable name (`name` or `{name}`) from the text following a `$`
+fn split_variable(input: &str) -> Option<(&str, &str)> {
+    if let Some(braced) = input.strip_prefix('{') {
+        let (name, after) = braced.split_once('}')?;
+        return is_variable_name(name).then_some((name, after));
+    }
+
+    let end = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(input.len());
+    let name = &input[..end];
+    is_variable_name(name).then_some((name, &input[end..]))
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The regex capture used for a variable
+fn variable_pattern(name: &str) -> &'static str {
+    match name {
+        "status" => r"(\d{3}|-)",
+        "body_bytes_sent" | "bytes_sent" | "request_length" => r"(\d+|-)",
+        "remote_addr" | "remote_user" | "request_time" | "upstream_response_time" => r"(\S*)",
+        "time_local" => r"([^\]]*)",
+        _ => r"(.*?)",
+    }
+}
+
+/// Store a variable under its field name with a typed value
+fn insert_variable(fields: &mut Fields, variable: &str, value: &str) {
+    match variable {
+        "request" => match split_request(value) {
+            Some((method, path, protocol)) => {
+                fields.insert("method", method);
+                fields.insert("path", path);
+                if let Some(protocol) = protocol {
+                    fields.insert("protocol", protocol);
+                }
+            }
+            None => fields.insert("request", value),
+        },
+        "time_local" => fields.insert("time", typed(value, parse_time_local)),
+        "time_iso8601" => fields.insert("time", typed(value, parse_time_iso8601)),
+        "request_method" => fields.insert("method", value),
+        "request_uri" | "uri" => fields.insert("path", value),
+        "server_protocol" => fields.insert("protocol", value),
+        "http_referer" => fields.insert("referer", value),
+        "http_user_agent" => fields.insert("user_agent", value),
+        "body_bytes_sent" | "bytes_sent" => fields.insert("bytes", typed(value, parse_int)),
+        "status" | "request_length" => fields.insert(variable, typed(value, parse_int)),
+        "request_time" | "upstream_response_time" => {
+            fields.insert(variable, typed(value, parse_float))
+        }
+        _ => fields.insert(variable, value),
+    }
+}
+
+/// Split `GET /path HTTP/1.1` into method, path and protocol
+fn split_request(request: &str) -> Option<(&str, &str, Option<&str>)> {
+    let mut parts = request.split(' ');
+    let method = parts
+        .next()
+        .filter(|method| !method.is_empty() && method.chars().all(|c| c.is_ascii_uppercase()))?;
+    let path = parts.next().filter(|path| !path.is_empty())?;
+    let protocol = parts.next();
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((method, path, protocol))
+}
+
+fn typed(value: &str, parse: fn(&str) -> Option<FieldValue>) -> FieldValue {
+    parse(value).unwrap_or_else(|| FieldValue::from(value))
+}
+
+fn parse_int(value: &str) -> Option<FieldValue> {
+    value.parse::<i64>().ok().map(FieldValue::Int)
+}
+
+fn parse_float(value: &str) -> Option<FieldValue> {
+    value.parse::<f64>().ok().map(FieldValue::Float)
+}
+
+fn parse_time_local(value: &str) -> Option<FieldValue> {
+    DateTime::parse_from_str(value, TIME_LOCAL_FORMAT)
+        .ok()
+        .map(FieldValue::Timestamp)
+}
+
+fn parse_time_iso8601(value: &str) -> Option<FieldValue> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(FieldValue::Timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combined_format() {
+        let line = r#"203.0.113.7 - alice [10/Oct/2023:13:55:36 -0700] "GET /index.html?q=1 HTTP/1.1" 200 2326 "https://example.com/" "Mozilla/5.0 (X11; Linux x86_64)""#;
+        let fields = AccessLogParser::combined().parse(line).unwrap();
+
+        assert_eq!(fields.get_str("remote_addr"), Some("203.0.113.7"));
+        assert_eq!(fields.get_str("remote_user"), Some("alice"));
+        assert_eq!(
+            fields.get("time"),
+            Some(&FieldValue::Timestamp(
+                DateTime::parse_from_rfc3339("2023-10-10T13:55:36-07:00").unwrap()
+            ))
+        );
+        assert_eq!(fields.get_str("method"), Some("GET"));
+        assert_eq!(fields.get_str("path"), Some("/index.html?q=1"));
+        assert_eq!(fields.get_str("protocol"), Some("HTTP/1.1"));
+        assert_eq!(fields.get("status"), Some(&FieldValue::Int(200)));
+        assert_eq!(fields.get("bytes"), Some(&FieldValue::Int(2326)));
+        assert_eq!(fields.get_str("referer"), Some("https://example.com/"));
+        assert_eq!(
+            fields.get_str("user_agent"),
+            Some("Mozilla/5.0 (X11; Linux x86_64)")
+        );
+    }
+
+    #[test]
+    fn test_common_format_with_dashes() {
+        let line = r#"127.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "POST /api HTTP/2.0" 204 -"#;
+        let fields = AccessLogParser::common().parse(line).unwrap();
+
+        assert!(!fields.contains_key("remote_user"));
+        assert!(!fields.contains_key("bytes"));
+        assert_eq!(fields.get_str("method"), Some("POST"));
+        assert_eq!(fields.get("status"), Some(&FieldValue::Int(204)));
+    }
+
+    #[test]
+    fn test_malformed_request_line() {
+        let line = r#"10.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "\x16\x03\x01" 400 157"#;
+        let fields = AccessLogParser::common().parse(line).unwrap();
+
+        assert_eq!(fields.get_str("request"), Some(r"\x16\x03\x01"));
+        assert!(!fields.contains_key("method"));
+        assert_eq!(fields.get("status"), Some(&FieldValue::Int(400)));
+    }
+
+    #[test]
+    fn test_custom_nginx_format() {
+        let parser = AccessLogParser::nginx_format(
+            r#"$remote_addr [$time_iso8601] "$request_method $uri" $status ${request_time}s upstream=$upstream_addr"#,
+        )
+        .unwrap();
+        let fields = parser
+            .parse(r#"::1 [2023-10-10T13:55:36+00:00] "GET /health" 200 0.004s upstream=10.0.0.5:8080"#)
+            .unwrap();
+
+        assert_eq!(fields.get_str("remote_addr"), Some("::1"));
+        assert!(matches!(fields.get("time"), Some(FieldValue::Timestamp(_))));
+        assert_eq!(fields.get_str("method"), Some("GET"));
+        assert_eq!(fields.get_str("path"), Some("/health"));
+        assert_eq!(fields.get("request_time"), Some(&FieldValue::Float(0.004)));
+        assert_eq!(fields.get_str("upstream_addr"), Some("10.0.0.5:8080"));
+    }
+
+    #[test]
+    fn test_unparseable_time_is_kept_as_text() {
+        let line = r#"127.0.0.1 - - [yesterday] "GET / HTTP/1.1" 200 5"#;
+        let fields = AccessLogParser::common().parse(line).unwrap();
+        assert_eq!(fields.get_str("time"), Some("yesterday"));
+    }
+
+    #[test]
+    fn test_builtin_formats() {
+        assert_eq!(AccessLogParser::common().variables.len(), 6);
+        assert_eq!(AccessLogParser::combined().variables.len(), 8);
+    }
+
+    #[test]
+    fn test_non_matching_line() {
+        assert!(
+            AccessLogParser::combined()
+                .parse("not an access log")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_formats() {
+        assert!(matches!(
+            AccessLogParser::nginx_format("no variables here"),
+            Err(Error::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            AccessLogParser::nginx_format("${unterminated"),
+            Err(Error::InvalidFormat { .. })
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+//! Parser for Linux audit log lines such as those in `/var/log/audit/audit.log`.
+
+use super::Parser;
+use crate::record::{FieldValue, Fields};
+use crate::timestamp::parse_timestamp;
+
+/// Fields that auditd hex encodes when their value contains spaces or other special characters.
+const ENCODED_FIELDS: &[&str] = &[
+    "proctitle",
+    "cmd",
+    "comm",
+    "cwd",
+    "data",
+    "dir",
+    "exe",
+    "file",
+    "name",
+    "path",
+    "acct",
+    "key",
+    "ocomm",
+    "watch",
+    "vm",
+    "sw",
+    "root_dir",
+];
+
+/// Separates the raw fields from the interpreted ones in the enriched log format.
+const ENRICHED_SEPARATOR: char = '\u{1d}';
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AuditLine {
+    /// The record type, such as `SYSCALL` or `PATH`.
+    pub(crate) kind: String,
+    /// The `seconds.millis` part of the event id.
+    pub(crate) timestamp: String,
+    /// The serial part of the event id, shared by all records of one event.
+    pub(crate) serial: u64,
+    /// The decoded `key=value` fields after the event id.
+    pub(crate) fields: Fields,
+}
+
+/// Parses a single audit log line into fields.
+///
+/// Produces `type`, `timestamp`, `serial` and every `key=value` pair of the
+/// line. Values are strings, with hex encoded values such as `proctitle`
+/// decoded. The quoted `msg='...'` of user space records becomes a nested map.
+///
+/// To combine all records of one event into a single record, use
+/// [`StreamConfig::audit_events`](crate::StreamConfig::audit_events).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditParser;
+
+impl AuditParser {
+    /// Creates an audit log parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Parser for AuditParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        let line = parse_audit_line(line)?;
+        let mut fields = Fields::new();
+        fields.insert("type", line.kind);
+        fields.insert("timestamp", audit_timestamp(&line.timestamp));
+        fields.insert("serial", line.serial as i64);
+        fields.extend(line.fields);
+        Some(fields)
+    }
+}
+
+/// Convert the event time to a timestamp, keeping the text if it does not parse
+pub(crate) fn audit_timestamp(timestamp: &str) -> FieldValue {
+    parse_timestamp(timestamp)
+        .map(FieldValue::Timestamp)
+        .unwrap_or_else(|| FieldValue::from(timestamp))
+}
+
+/// Parse `type=KIND msg=audit(seconds.millis:serial): key=value ...`
+pub(crate) fn parse_audit_line(line: &str) -> Option<AuditLine> {
+    let rest = line.trim_start().strip_prefix("type=")?;
+    let (kind, rest) = rest.split_once(' ')?;
+    let rest = rest.trim_start().strip_prefix("msg=audit(")?;
+    let (id, rest) = rest.split_once(')')?;
+    let (timestamp, serial) = id.split_once(':')?;
+    let serial = serial.parse().ok()?;
+    let rest = rest.strip_prefix(':').unwrap_or(rest);
+
+    let mut fields = Fields::new();
+    for (key, value) in split_pairs(&rest.replace(ENRICHED_SEPARATOR, " ")) {
+        let value = match value {
+            Value::Quoted(value) => FieldValue::from(value),
+            Value::Nested(value) if key == "msg" => {
+                let mut nested = Fields::new();
+                for (key, value) in split_pairs(&value) {
+                    nested.insert(key.clone(), decode_value(&key, value));
+                }
+                FieldValue::Map(nested)
+            }
+            value => decode_value(&key, value),
+        };
+        fields.insert(key, value);
+    }
+
+    Some(AuditLine {
+        kind: kind.to_string(),
+        timestamp: timestamp.to_string(),
+        serial,
+        fields,
+    })
+}
+
+/// A value as written in the log.
+enum Value {
+    Bare(String),
+    Quoted(String),
+    Nested(String),
+}
+
+/// Decode a value, turning unquoted hex in encoded fields back into text
+fn decode_value(key: &str, value: Value) -> FieldValue {
+    match value {
+        Value::Bare(value) if ENCODED_FIELDS.contains(&key) => {
+            let decoded = decode_hex(&value).map(|text| {
+                // The process title separates arguments with NUL bytes
+                if key == "proctitle" {
+                    text.replace('\0', " ")
+                } else {
+                    text
+                }
+            });
+            FieldValue::from(decoded.unwrap_or(value))
+        }
+        Value::Bare(value) | Value::Quoted(value) | Value::Nested(value) => FieldValue::from(value),
+    }
+}
+
+/// Decode an even length string of hex digits
+fn decode_hex(value: &str) -> Option<String> {
+    if value.is_empty()
+        || !value.len().is_multiple_of(2)
+        || !value.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    let bytes = (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Split `key=value` pairs, where values may be bare, `"quoted"` or `'nested'`
+fn split_pairs(input: &str) -> Vec<(String, Value)> {
+    let mut pairs = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        let token_end = rest.find([' ', '=']).unwrap_or(rest.len());
+        let key = &rest[..token_end];
+        rest = &rest[token_end..];
+
+        let Some(after) = rest.strip_prefix('=') else {
+            // A word without a value, skip it
+            rest = rest.trim_start();
+            continue;
+        };
+
+        let (value, after) = match after.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &after[1..];
+                let end = inner.find(quote).unwrap_or(inner.len());
+                let value = inner[..end].to_string();
+                let after = inner.get(end + 1..).unwrap_or("");
+                if quote == '"' {
+                    (Value::Quoted(value), after)
+                } else {
+                    (Value::Nested(value), after)
+                }
+            }
+            _ => {
+                let end = after.find(' ').unwrap_or(after.len());
+                (Value::Bare(after[..end].to_string()), &after[end..])
+            }
+        };
+
+        if !key.is_empty() {
+            pairs.push((key.to_string(), value));
+        }
+        rest = after.trim_start();
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_syscall_line() {
+        let line = r#"type=SYSCALL msg=audit(1364481363.243:24287): arch=c000003e syscall=2 success=no exit=-13 comm="cat" exe="/usr/bin/cat" key="sshd_config""#;
+        let fields = AuditParser::new().parse(line).unwrap();
+
+        assert_eq!(fields.get_str("type"), Some("SYSCALL"));
+        assert_eq!(fields.get("serial"), Some(&FieldValue::Int(24287)));
+        assert!(matches!(
+            fields.get("timestamp"),
+            Some(FieldValue::Timestamp(_))
+        ));
+        assert_eq!(fields.get_str("syscall"), Some("2"));
+        assert_eq!(fields.get_str("comm"), Some("cat"));
+        assert_eq!(fields.get_str("key"), Some("sshd_config"));
+    }
+
+    #[test]
+    fn test_hex_encoded_fields() {
+        let line = "type=PROCTITLE msg=audit(1364481363.243:24287): proctitle=636174002F6574632F7373682F737368645F636F6E666967";
+        let fields = AuditParser::new().parse(line).unwrap();
+        assert_eq!(
+            fields.get_str("proctitle"),
+            Some("cat /etc/ssh/sshd_config")
+        );
+
+        let line = "type=PATH msg=audit(1364481363.243:24287): item=0 name=2F746D702F6D792066696C65 inode=409248 mode=0100600";
+        let fields = AuditParser::new().parse(line).unwrap();
+        assert_eq!(fields.get_str("name"), Some("/tmp/my file"));
+        // Values of fields that are never encoded stay as written
+        assert_eq!(fields.get_str("inode"), Some("409248"));
+    }
+
+    #[test]
+    fn test_nested_user_message_and_enriched_fields() {
+        let line = "type=USER_LOGIN msg=audit(1700000000.100:42): pid=812 uid=0 msg='op=login acct=\"alice\" exe=\"/usr/sbin/sshd\" res=success'\u{1d}UID=\"root\"";
+        let fields = AuditParser::new().parse(line).unwrap();
+
+        let Some(FieldValue::Map(msg)) = fields.get("msg") else {
+            panic!("Expected nested msg");
+        };
+        assert_eq!(msg.get_str("op"), Some("login"));
+        assert_eq!(msg.get_str("acct"), Some("alice"));
+        assert_eq!(msg.get_str("res"), Some("success"));
+        assert_eq!(fields.get_str("UID"), Some("root"));
+    }
+
+    #[test]
+    fn test_non_audit_lines() {
+        let parser = AuditParser::new();
+        assert!(parser.parse("plain text").is_none());
+        assert!(parser.parse("type=SYSCALL no id here").is_none());
+        assert!(parse_audit_line("type=EOE msg=audit(1.0:x):").is_none());
+    }
+}
@@ -0,0 +1,355 @@
+//! Grok patterns: reusable named regular expressions referenced as `%{NAME:field:type}`.
+
+use super::Parser;
+use super::pattern::{FieldType, RegexParser};
+use crate::error::{Error, Result};
+use crate::record::Fields;
+use std::collections::HashMap;
+
+/// How deeply patterns may reference other patterns before compilation gives up.
+const MAX_DEPTH: usize = 32;
+
+/// The built-in pattern library, following the names of the common grok patterns.
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("USERNAME", r"[a-zA-Z0-9._-]+"),
+    ("USER", "%{USERNAME}"),
+    ("INT", r"[+-]?\d+"),
+    ("BASE10NUM", r"[+-]?(?:\d+(?:\.\d*)?|\.\d+)"),
+    ("NUMBER", "%{BASE10NUM}"),
+    ("BASE16NUM", r"[+-]?(?:0x)?[0-9A-Fa-f]+"),
+    ("POSINT", r"[1-9][0-9]*"),
+    ("NONNEGINT", r"[0-9]+"),
+    ("WORD", r"\b\w+\b"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'"#),
+    ("QS", "%{QUOTEDSTRING}"),
+    (
+        "UUID",
+        r"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}",
+    ),
+    ("MAC", r"(?:[A-Fa-f0-9]{2}[:-]){5}[A-Fa-f0-9]{2}"),
+    (
+        "IPV4",
+        r"(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)",
+    ),
+    (
+        "IPV6",
+        r"(?:[0-9A-Fa-f]{0,4}:){2,6}%{IPV4}|(?:[0-9A-Fa-f]{0,4}:){2,7}[0-9A-Fa-f]{0,4}(?:%\w+)?",
+    ),
+    ("IP", "%{IPV6}|%{IPV4}"),
+    (
+        "HOSTNAME",
+        r"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?",
+    ),
+    ("IPORHOST", "%{IP}|%{HOSTNAME}"),
+    ("HOSTPORT", "%{IPORHOST}:%{POSINT}"),
+    ("EMAILADDRESS", r"[a-zA-Z0-9._%+-]+@%{HOSTNAME}"),
+    ("UNIXPATH", r"(?:/[\w%!$@:.,+~-]*)+"),
+    ("WINPATH", r"(?:[A-Za-z]:|\\)(?:\\[^\\?*]*)+"),
+    ("PATH", "%{UNIXPATH}|%{WINPATH}"),
+    ("URIPROTO", r"[A-Za-z][A-Za-z0-9+.-]*"),
+    ("URIHOST", "%{IPORHOST}(?::%{POSINT})?"),
+    ("URIPATH", r"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_-]*)+"),
+    ("URIPARAM", r"\?[A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?\[\]<>-]*"),
+    ("URIPATHPARAM", "%{URIPATH}(?:%{URIPARAM})?"),
+    (
+        "URI",
+        "%{URIPROTO}://(?:%{USER}(?::[^@]*)?@)?(?:%{URIHOST})?(?:%{URIPATHPARAM})?",
+    ),
+    (
+        "MONTH",
+        r"\b(?:[Jj]an(?:uary)?|[Ff]eb(?:ruary)?|[Mm]ar(?:ch)?|[Aa]pr(?:il)?|[Mm]ay|[Jj]un(?:e)?|[Jj]ul(?:y)?|[Aa]ug(?:ust)?|[Ss]ep(?:tember)?|[Oo]ct(?:ober)?|[Nn]ov(?:ember)?|[Dd]ec(?:ember)?)\b",
+    ),
+    ("MONTHNUM", r"(?:1[0-2]|0?[1-9])"),
+    ("MONTHDAY", r"(?:3[01]|[12][0-9]|0?[1-9])"),
+    (
+        "DAY",
+        r"(?:Mon(?:day)?|Tue(?:sday)?|Wed(?:nesday)?|Thu(?:rsday)?|Fri(?:day)?|Sat(?:urday)?|Sun(?:day)?)",
+    ),
+    ("YEAR", r"\d\d(?:\d\d)?"),
+    ("HOUR", r"(?:2[0123]|[01]?[0-9])"),
+    ("MINUTE", r"(?:[0-5][0-9])"),
+    ("SECOND", r"(?:(?:[0-5]?[0-9]|60)(?:[:.,][0-9]+)?)"),
+    ("TIME", "%{HOUR}:%{MINUTE}(?::%{SECOND})?"),
+    ("DATE_US", "%{MONTHNUM}[/-]%{MONTHDAY}[/-]%{YEAR}"),
+    ("DATE_EU", "%{MONTHDAY}[./-]%{MONTHNUM}[./-]%{YEAR}"),
+    ("ISO8601_TIMEZONE", "(?:Z|[+-]%{HOUR}(?::?%{MINUTE}))"),
+    (
+        "TIMESTAMP_ISO8601",
+        "%{YEAR}-%{MONTHNUM}-%{MONTHDAY}[T ]%{HOUR}:?%{MINUTE}(?::?%{SECOND})?%{ISO8601_TIMEZONE}?",
+    ),
+    ("HTTPDATE", "%{MONTHDAY}/%{MONTH}/%{YEAR}:%{TIME} %{INT}"),
+    ("SYSLOGTIMESTAMP", "%{MONTH} +%{MONTHDAY} %{TIME}"),
+    (
+        "LOGLEVEL",
+        r"(?i:trace|debug|info|notice|warn(?:ing)?|err(?:or)?|crit(?:ical)?|fatal|severe|emerg(?:ency)?|alert)",
+    ),
+    (
+        "COMMONAPACHELOG",
+        r#"%{IPORHOST:clientip} %{USER:ident} %{USER:auth} \[%{HTTPDATE:timestamp:timestamp}\] "(?:%{WORD:verb} %{NOTSPACE:request}(?: HTTP/%{NUMBER:httpversion})?|%{DATA:rawrequest})" %{NUMBER:response:int} (?:%{NUMBER:bytes:int}|-)"#,
+    ),
+    (
+        "COMBINEDAPACHELOG",
+        r#"%{COMMONAPACHELOG} "%{DATA:referrer}" "%{DATA:agent}""#,
+    ),
+];
+
+/// A library of grok patterns that compiles grok expressions into parsers.
+///
+/// Starts with the built-in patterns (`IPV4`, `WORD`, `NUMBER`,
+/// `TIMESTAMP_ISO8601`, `COMBINEDAPACHELOG`, ...) and can be extended with
+/// custom ones, which may reference other patterns themselves.
+#[derive(Debug, Clone)]
+pub struct GrokPatterns {
+    patterns: HashMap<String, String>,
+}
+
+impl Default for GrokPatterns {
+    fn default() -> Self {
+        Self {
+            patterns: BUILTIN_PATTERNS
+                .iter()
+                .map(|(name, pattern)| (name.to_string(), pattern.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl GrokPatterns {
+    /// Creates a library with the built-in patterns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a pattern.
+    pub fn add(mut self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.patterns.insert(name.into(), pattern.into());
+        self
+    }
+
+    /// Compiles a grok expression into a parser.
+    ///
+    /// `%{NAME}` matches a pattern without capturing it, `%{NAME:field}`
+    /// stores the match as `field` and `%{NAME:field:type}` also converts it,
+    /// where `type` is `int`, `float`, `bool`, `timestamp` or `string`. Plain
+    /// regex named groups are captured as well.
+    ///
+    /// Field names may contain characters regex group names cannot, such as
+    /// `%{IP:client.ip}` or `%{WORD:http-method}`.
+    pub fn compile(&self, expression: &str) -> Result<GrokParser> {
+        let mut fields = Vec::new();
+        let regex = self.expand(expression, 0, &mut fields)?;
+
+        let mut parser = RegexParser::new(&regex)?;
+        for (index, (field, field_type)) in fields.into_iter().enumerate() {
+            if let Some(field_type) = field_type {
+                parser = parser.field_type(field.as_str(), field_type);
+            }
+            parser = parser.alias(group_name(index), field);
+        }
+        Ok(GrokParser { parser })
+    }
+
+    /// Replace every `%{...}` reference with its regex, recording captured fields
+    /// in the order of their generated group names
+    fn expand(
+        &self,
+        expression: &str,
+        depth: usize,
+        fields: &mut Vec<(String, Option<FieldType>)>,
+    ) -> Result<String> {
+        if depth > MAX_DEPTH {
+            return Err(invalid(format!(
+                "patterns nested more than {} deep in `{}`",
+                MAX_DEPTH, expression
+            )));
+        }
+
+        let mut regex = String::new();
+        let mut rest = expression;
+
+        while let Some(start) = rest.find("%{") {
+            regex.push_str(&rest[..start]);
+            let reference = &rest[start + 2..];
+            let end = reference
+                .find('}')
+                .ok_or_else(|| invalid(format!("unterminated `%{{` in `{}`", expression)))?;
+
+            let mut parts = reference[..end].splitn(3, ':');
+            let name = parts.next().unwrap_or_default();
+            let field = parts.next().filter(|field| !field.is_empty());
+            let field_type = parts.next().map(parse_field_type).transpose()?;
+
+            let pattern = self
+                .patterns
+                .get(name)
+                .ok_or_else(|| invalid(format!("unknown pattern `{}`", name)))?;
+            let expanded = self.expand(pattern, depth + 1, fields)?;
+
+            match field {
+                Some(field) => {
+                    let group = group_name(fields.len());
+                    regex.push_str(&format!("(?P<{}>{})", group, expanded));
+                    fields.push((field.to_string(), field_type));
+                }
+                None => regex.push_str(&format!("(?:{})", expanded)),
+            }
+            rest = &reference[end + 1..];
+        }
+        regex.push_str(rest);
+
+        Ok(regex)
+    }
+}
+
+/// Parses lines with a grok expression such as `%{IPV4:client} %{WORD:method}`.
+///
+/// See [`GrokPatterns::compile`] for the expression syntax.
+#[derive(Debug, Clone)]
+pub struct GrokParser {
+    parser: RegexParser,
+}
+
+impl GrokParser {
+    /// Compiles a grok expression using the built-in patterns.
+    pub fn new(expression: &str) -> Result<Self> {
+        GrokPatterns::new().compile(expression)
+    }
+}
+
+impl Parser for GrokParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        self.parser.parse(line)
+    }
+}
+
+/// The regex group name generated for the grok field at `index`
+fn group_name(index: usize) -> String {
+    format!("__grok{}", index)
+}
+
+fn parse_field_type(name: &str) -> Result<FieldType> {
+    match name {
+        "string" => Ok(FieldType::String),
+        "int" => Ok(FieldType::Int),
+        "float" => Ok(FieldType::Float),
+        "bool" => Ok(FieldType::Bool),
+        "timestamp" => Ok(FieldType::Timestamp),
+        other => Err(invalid(format!("unknown field type `{}`", other))),
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidFormat { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::FieldValue;
+
+    #[test]
+    fn test_grok_fields_and_types() {
+        let parser = GrokParser::new(
+            r"^%{TIMESTAMP_ISO8601:time:timestamp} %{LOGLEVEL:level} %{IPV4:client} %{WORD:method} %{URIPATHPARAM:path} %{INT:status:int} %{NUMBER:took:float}$",
+        )
+        .unwrap();
+
+        let fields = parser
+            .parse("2024-03-01T10:00:00Z INFO 10.1.2.3 GET /api/users?id=7 200 0.031")
+            .unwrap();
+        assert!(matches!(fields.get("time"), Some(FieldValue::Timestamp(_))));
+        assert_eq!(fields.get_str("level"), Some("INFO"));
+        assert_eq!(fields.get_str("client"), Some("10.1.2.3"));
+        assert_eq!(fields.get_str("method"), Some("GET"));
+        assert_eq!(fields.get_str("path"), Some("/api/users?id=7"));
+        assert_eq!(fields.get("status"), Some(&FieldValue::Int(200)));
+        assert_eq!(fields.get("took"), Some(&FieldValue::Float(0.031)));
+
+        let keys: Vec<&str> = fields.iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "time", "level", "client", "method", "path", "status", "took"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_names_that_are_not_group_names() {
+        let parser =
+            GrokParser::new(r"^%{IP:client.ip} %{WORD:http-method} %{INT:http.status:int}$")
+                .unwrap();
+
+        let fields = parser.parse("10.0.0.1 GET 404").unwrap();
+        assert_eq!(fields.get_str("client.ip"), Some("10.0.0.1"));
+        assert_eq!(fields.get_str("http-method"), Some("GET"));
+        assert_eq!(fields.get("http.status"), Some(&FieldValue::Int(404)));
+        assert_eq!(fields.len(), 3);
+    }
+
+    #[test]
+    fn test_combined_apache_log() {
+        let parser = GrokParser::new("%{COMBINEDAPACHELOG}").unwrap();
+        let line = r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326 "http://www.example.com/start.html" "Mozilla/4.08""#;
+        let fields = parser.parse(line).unwrap();
+
+        assert_eq!(fields.get_str("clientip"), Some("127.0.0.1"));
+        assert_eq!(fields.get_str("auth"), Some("frank"));
+        assert!(matches!(
+            fields.get("timestamp"),
+            Some(FieldValue::Timestamp(_))
+        ));
+        assert_eq!(fields.get_str("request"), Some("/apache_pb.gif"));
+        assert_eq!(fields.get("response"), Some(&FieldValue::Int(200)));
+        assert_eq!(fields.get("bytes"), Some(&FieldValue::Int(2326)));
+        assert_eq!(fields.get_str("agent"), Some("Mozilla/4.08"));
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let parser = GrokPatterns::new()
+            .add("ORDER_ID", r"ORD-\d{6}")
+            .add("ORDER", "%{ORDER_ID:order} for %{EMAILADDRESS:email}")
+            .compile(r"placed %{ORDER} at (?P<store>\w+)")
+            .unwrap();
+
+        let fields = parser
+            .parse("order placed ORD-123456 for jo@example.com at downtown")
+            .unwrap();
+        assert_eq!(fields.get_str("order"), Some("ORD-123456"));
+        assert_eq!(fields.get_str("email"), Some("jo@example.com"));
+        assert_eq!(fields.get_str("store"), Some("downtown"));
+    }
+
+    #[test]
+    fn test_ip_patterns() {
+        let parser = GrokParser::new(r"^%{IP:ip}$").unwrap();
+        assert_eq!(
+            parser.parse("2001:db8::1").unwrap().get_str("ip"),
+            Some("2001:db8::1")
+        );
+        assert_eq!(
+            parser.parse("::ffff:192.0.2.1").unwrap().get_str("ip"),
+            Some("::ffff:192.0.2.1")
+        );
+        assert!(parser.parse("300.1.1.1").is_none());
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        for expression in ["%{NOPE:x}", "%{WORD:x:weird}", "%{WORD", "%{LOOP}"] {
+            let result = GrokPatterns::new()
+                .add("LOOP", "a%{LOOP}")
+                .compile(expression);
+            assert!(
+                matches!(result, Err(Error::InvalidFormat { .. })),
+                "{} should fail",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Parser for logfmt formatted lines such as `level=info msg="started" port=8080`.
+
+use super::Parser;
+use crate::record::{FieldValue, Fields};
+
+/// Parses logfmt `key=value` pairs into fields, in the order they appear.
+///
+/// Values may be bare or double quoted with backslash escapes. Keys without a
+/// value (`debug` in `level=info debug`) are stored as `true`. Values are kept as
+/// strings; use [`FieldValue::as_i64`] and friends to read numbers.
+///
+/// Lines without a single `key=value` pair, such as plain text, do not parse.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogfmtParser;
+
+impl LogfmtParser {
+    /// Creates a logfmt parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Parser for LogfmtParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        parse_logfmt(line)
+    }
+}
+
+/// Parse all key/value pairs of a logfmt line, or nothing if it has no `key=value` pair
+fn parse_logfmt(line: &str) -> Option<Fields> {
+    let mut fields = Fields::new();
+    let mut has_pair = false;
+    let mut chars = line.chars().peekable();
+
+    loop {
+        // Skip whitespace between pairs
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+
+        if chars.peek() != Some(&'=') {
+            if !key.is_empty() {
+                fields.insert(key, FieldValue::Bool(true));
+            }
+            continue;
+        }
+        chars.next(); // Consume '='
+
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            parse_quoted(&mut chars)
+        } else {
+            let mut value = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            value
+        };
+
+        // A stray '=' without a key carries no information
+        if !key.is_empty() {
+            fields.insert(key, FieldValue::String(value));
+            has_pair = true;
+        }
+    }
+
+    has_pair.then_some(fields)
+}
+
+/// Parse a quoted value after the opening quote, up to the closing quote or end of line
+fn parse_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut value = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('u') => {
+                    // Only hex digits, so a short escape never takes the closing quote
+                    let mut hex = String::new();
+                    while hex.len() < 4
+                        && let Some(digit) = chars.next_if(char::is_ascii_hexdigit)
+                    {
+                        hex.push(digit);
+                    }
+                    let decoded = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .filter(|_| hex.len() == 4)
+                        .and_then(char::from_u32);
+                    match decoded {
+                        Some(decoded) => value.push(decoded),
+                        None => {
+                            value.push_str("\\u");
+                            value.push_str(&hex);
+                        }
+                    }
+                }
+                Some(other) => value.push(other),
+                None => value.push('\\'),
+            },
+            other => value.push(other),
+        }
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Fields {
+        LogfmtParser::new().parse(line).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_simple_pairs() {
+        let fields = parse("level=info msg=started port=8080");
+        assert_eq!(fields.get_str("level"), Some("info"));
+        assert_eq!(fields.get_str("msg"), Some("started"));
+        assert_eq!(fields.get("port").and_then(FieldValue::as_i64), Some(8080));
+    }
+
+    #[test]
+    fn test_field_order_is_preserved() {
+        let fields = parse("z=1 a=2 m=3");
+        let keys: Vec<&str> = fields.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["z", "a", "m"]);
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let fields = parse(r#"level=info msg="server started" path="/a b""#);
+        assert_eq!(fields.get_str("msg"), Some("server started"));
+        assert_eq!(fields.get_str("path"), Some("/a b"));
+    }
+
+    #[test]
+    fn test_escapes_in_quoted_values() {
+        let fields = parse(r#"msg="say \"hi\"\n\tdone\\" unicode="café""#);
+        assert_eq!(fields.get_str("msg"), Some("say \"hi\"\n\tdone\\"));
+        assert_eq!(fields.get_str("unicode"), Some("café"));
+    }
+
+    #[test]
+    fn test_short_unicode_escape_is_kept() {
+        let fields = parse(r#"msg="a\u12" level=warn note="é\uzz" ok="é""#);
+        assert_eq!(fields.get_str("msg"), Some(r"a\u12"));
+        assert_eq!(fields.get_str("level"), Some("warn"));
+        assert_eq!(fields.get_str("note"), Some(r"é\uzz"));
+        assert_eq!(fields.get_str("ok"), Some("é"));
+    }
+
+    #[test]
+    fn test_bare_keys() {
+        let fields = parse("level=debug verbose cached");
+        assert_eq!(fields.get("verbose"), Some(&FieldValue::Bool(true)));
+        assert_eq!(fields.get("cached"), Some(&FieldValue::Bool(true)));
+    }
+
+    #[test]
+    fn test_empty_values() {
+        let fields = parse(r#"a= b="" c=x"#);
+        assert_eq!(fields.get_str("a"), Some(""));
+        assert_eq!(fields.get_str("b"), Some(""));
+        assert_eq!(fields.get_str("c"), Some("x"));
+    }
+
+    #[test]
+    fn test_unterminated_quote_takes_rest_of_line() {
+        let fields = parse(r#"level=error msg="unterminated value"#);
+        assert_eq!(fields.get_str("msg"), Some("unterminated value"));
+    }
+
+    #[test]
+    fn test_stray_equals_is_ignored() {
+        let fields = parse("=oops level=warn");
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields.get_str("level"), Some("warn"));
+    }
+
+    #[test]
+    fn test_duplicate_keys_keep_last_value() {
+        let fields = parse("a=1 a=2");
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields.get_str("a"), Some("2"));
+    }
+
+    #[test]
+    fn test_empty_line_does_not_parse() {
+        assert!(LogfmtParser::new().parse("   ").is_none());
+    }
+
+    #[test]
+    fn test_plain_text_does_not_parse() {
+        assert!(LogfmtParser::new().parse("hello world").is_none());
+        assert!(LogfmtParser::new().parse("=oops only").is_none());
+        assert!(LogfmtParser::new().parse("hello level=info").is_some());
+    }
+}
@@ -0,0 +1,57 @@
+//! Parsers that turn record text into structured fields.
+
+mod access;
+mod audit;
+mod grok;
+mod logfmt;
+mod pattern;
+mod syslog;
+
+pub use access::{AccessLogParser, COMBINED_LOG_FORMAT, COMMON_LOG_FORMAT};
+pub use audit::AuditParser;
+pub use grok::{GrokParser, GrokPatterns};
+pub use logfmt::LogfmtParser;
+pub use pattern::{FieldType, RegexParser};
+pub use syslog::{SyslogFormat, SyslogParser};
+
+pub(crate) use audit::{AuditLine, audit_timestamp, parse_audit_line};
+
+use crate::record::Fields;
+
+/// Extracts structured fields from the text of a record.
+///
+/// A parser is configured on the stream with
+/// [`StreamConfig::parser`](crate::StreamConfig::parser) and runs in the reader
+/// task, so records arrive with their fields already filled in. Closures taking
+/// a line and returning `Option<Fields>` can be used as parsers directly, and
+/// the built-in parsers can also be added to a [`Pipeline`](crate::Pipeline).
+pub trait Parser: Send + Sync {
+    /// Parses a line, returning `None` if it is not in the expected format.
+    fn parse(&self, line: &str) -> Option<Fields>;
+}
+
+impl<F> Parser for F
+where
+    F: Fn(&str) -> Option<Fields> + Send + Sync,
+{
+    fn parse(&self, line: &str) -> Option<Fields> {
+        self(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_parser() {
+        let parser = |line: &str| {
+            let (key, value) = line.split_once(':')?;
+            Some([(key.trim(), value.trim())].into_iter().collect::<Fields>())
+        };
+
+        let fields = parser.parse("user: alice").unwrap();
+        assert_eq!(fields.get_str("user"), Some("alice"));
+        assert!(parser.parse("no separator").is_none());
+    }
+}
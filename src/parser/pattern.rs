@@ -0,0 +1,166 @@
+//! Parser that turns the named capture groups of a regular expression into fields.
+
+use super::Parser;
+use crate::error::Result;
+use crate::record::{FieldValue, Fields};
+use crate::timestamp::parse_timestamp;
+use regex::Regex;
+
+/// The type a captured string is converted to.
+///
+/// Values that do not convert are kept as strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldType {
+    /// Keep the captured text.
+    #[default]
+    String,
+    /// A signed integer.
+    Int,
+    /// A floating point number.
+    Float,
+    /// `true` or `false`.
+    Bool,
+    /// A timestamp in one of the common log formats, such as RFC 3339.
+    Timestamp,
+}
+
+impl FieldType {
+    /// Converts captured text to a value of this type.
+    pub fn coerce(&self, text: &str) -> FieldValue {
+        let converted = match self {
+            FieldType::String => None,
+            FieldType::Int => text.parse().ok().map(FieldValue::Int),
+            FieldType::Float => text.parse().ok().map(FieldValue::Float),
+            FieldType::Bool => text.parse().ok().map(FieldValue::Bool),
+            FieldType::Timestamp => parse_timestamp(text).map(FieldValue::Timestamp),
+        };
+        converted.unwrap_or_else(|| FieldValue::from(text))
+    }
+}
+
+/// Parses lines with a regular expression, storing each named capture group as a field.
+///
+/// Groups that did not take part in the match are left out. Captures are strings
+/// unless a type is set with [`field_type`](Self::field_type).
+///
+/// ```
+/// use log_reader::{FieldType, Parser, RegexParser};
+///
+/// let parser = RegexParser::new(r"^(?P<level>\w+) took (?P<ms>\d+)ms$")
+///     .unwrap()
+///     .field_type("ms", FieldType::Int);
+/// let fields = parser.parse("INFO took 12ms").unwrap();
+/// assert_eq!(fields.get("ms").and_then(|v| v.as_i64()), Some(12));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexParser {
+    regex: Regex,
+    types: Vec<(String, FieldType)>,
+    /// Field names for groups whose name is generated, such as grok's.
+    aliases: Vec<(String, String)>,
+}
+
+impl RegexParser {
+    /// Creates a parser from a regular expression with named capture groups.
+    pub fn new(pattern: &str) -> Result<Self> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            types: Vec::new(),
+            aliases: Vec::new(),
+        })
+    }
+
+    /// Stores the named group under a different field name.
+    pub(crate) fn alias(mut self, group: impl Into<String>, field: impl Into<String>) -> Self {
+        self.aliases.push((group.into(), field.into()));
+        self
+    }
+
+    fn field_name<'a>(&'a self, group: &'a str) -> &'a str {
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias == group)
+            .map_or(group, |(_, field)| field)
+    }
+
+    /// Sets the type the named group is converted to.
+    pub fn field_type(mut self, name: impl Into<String>, field_type: FieldType) -> Self {
+        let name = name.into();
+        self.types.retain(|(existing, _)| *existing != name);
+        self.types.push((name, field_type));
+        self
+    }
+
+    fn type_of(&self, name: &str) -> FieldType {
+        self.types
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, field_type)| *field_type)
+            .unwrap_or_default()
+    }
+}
+
+impl Parser for RegexParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        let captures = self.regex.captures(line)?;
+        let mut fields = Fields::new();
+
+        for group in self.regex.capture_names().flatten() {
+            if let Some(value) = captures.name(group) {
+                let name = self.field_name(group);
+                fields.insert(name, self.type_of(name).coerce(value.as_str()));
+            }
+        }
+
+        Some(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_captures_become_fields() {
+        let parser =
+            RegexParser::new(r"^\[(?P<level>\w+)\] (?:user=(?P<user>\w+) )?(?P<msg>.*)$").unwrap();
+
+        let fields = parser.parse("[WARN] user=bob disk almost full").unwrap();
+        assert_eq!(fields.get_str("level"), Some("WARN"));
+        assert_eq!(fields.get_str("user"), Some("bob"));
+        assert_eq!(fields.get_str("msg"), Some("disk almost full"));
+
+        let fields = parser.parse("[INFO] started").unwrap();
+        assert!(!fields.contains_key("user"));
+        assert!(parser.parse("no brackets").is_none());
+    }
+
+    #[test]
+    fn test_field_type_coercion() {
+        let parser = RegexParser::new(
+            r"^(?P<at>\S+) (?P<status>\d+) (?P<secs>\S+) (?P<cached>\w+) (?P<count>\S+)$",
+        )
+        .unwrap()
+        .field_type("at", FieldType::Timestamp)
+        .field_type("status", FieldType::Int)
+        .field_type("secs", FieldType::Float)
+        .field_type("cached", FieldType::Bool)
+        .field_type("count", FieldType::Int);
+
+        let fields = parser
+            .parse("2024-03-01T10:00:00Z 200 0.25 true n/a")
+            .unwrap();
+        assert!(matches!(fields.get("at"), Some(FieldValue::Timestamp(_))));
+        assert_eq!(fields.get("status"), Some(&FieldValue::Int(200)));
+        assert_eq!(fields.get("secs"), Some(&FieldValue::Float(0.25)));
+        assert_eq!(fields.get("cached"), Some(&FieldValue::Bool(true)));
+        // Values that do not convert stay strings
+        assert_eq!(fields.get_str("count"), Some("n/a"));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let pattern = String::from("(?P<open");
+        assert!(RegexParser::new(&pattern).is_err());
+    }
+}
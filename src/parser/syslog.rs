@@ -0,0 +1,444 @@
+//! Parsers for RFC 3164 (BSD) and RFC 5424 syslog lines.
+
+use super::Parser;
+use crate::record::{FieldValue, Fields};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Facility names indexed by facility code.
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+/// Severity names indexed by severity code.
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// RFC 3164 style timestamps: `Oct 11 22:14:15`, `Oct  1 22:14:15.123`,
+/// `Oct 11 2023 22:14:15` or an ISO 8601 timestamp as written by rsyslog.
+static BSD_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:[A-Z][a-z]{2} +\d{1,2}(?: \d{4})? \d{2}:\d{2}:\d{2}(?:\.\d+)?|\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?):?(?:\s+|$)",
+    )
+    .unwrap()
+});
+
+/// Tag with optional pid: `sshd[123]:`, `cron:` or `kernel[7]` without a colon.
+static BSD_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([^\s\[\]:]+)(?:\[([^\]]*)\]:?|:)(?:\s|$)").unwrap());
+
+/// RFC 5424 header start: a version followed by a timestamp or the nil value.
+static RFC5424_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([1-9]\d?) +(?:-|\d{4}-\d{2}-\d{2}T)").unwrap());
+
+/// Which syslog format a [`SyslogParser`] expects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// Detect RFC 5424 by its version field, otherwise parse as RFC 3164.
+    #[default]
+    Auto,
+    /// BSD syslog: `<PRI>Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`.
+    Rfc3164,
+    /// `<PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID SD MSG`.
+    Rfc5424,
+}
+
+/// Parses syslog lines into fields.
+///
+/// Produces `priority`, `facility` and `severity` (as names such as `daemon` and
+/// `err`) when a `<PRI>` is present, then `timestamp`, `hostname`, `app_name`,
+/// `procid` and `message`. RFC 5424 lines also produce `version`, `msgid` and
+/// `structured_data`, a map of SD-ID to its parameters. Nil values (`-`) are
+/// left out.
+///
+/// RFC 3164 parsing is lenient: the priority, hostname and tag are all
+/// optional, and whatever cannot be recognised is kept in `message`. Lines
+/// with neither a priority nor a timestamp are not treated as syslog.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyslogParser {
+    format: SyslogFormat,
+}
+
+impl SyslogParser {
+    /// Creates a parser that detects the format of each line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a parser for RFC 3164 lines only.
+    pub fn rfc3164() -> Self {
+        Self {
+            format: SyslogFormat::Rfc3164,
+        }
+    }
+
+    /// Creates a parser for RFC 5424 lines only.
+    pub fn rfc5424() -> Self {
+        Self {
+            format: SyslogFormat::Rfc5424,
+        }
+    }
+
+    /// Returns the format this parser expects.
+    pub fn format(&self) -> SyslogFormat {
+        self.format
+    }
+}
+
+impl Parser for SyslogParser {
+    fn parse(&self, line: &str) -> Option<Fields> {
+        let mut fields = Fields::new();
+        let rest = parse_priority(line, &mut fields);
+
+        match self.format {
+            SyslogFormat::Rfc3164 => parse_rfc3164(rest, &mut fields)?,
+            SyslogFormat::Rfc5424 => parse_rfc5424(rest, &mut fields)?,
+            SyslogFormat::Auto => {
+                if !(RFC5424_VERSION.is_match(rest) && parse_rfc5424(rest, &mut fields).is_some()) {
+                    parse_rfc3164(rest, &mut fields)?;
+                }
+            }
+        }
+
+        Some(fields)
+    }
+}
+
+/// Parse a leading `<PRI>`, returning the rest of the line
+fn parse_priority<'a>(line: &'a str, fields: &mut Fields) -> &'a str {
+    let Some(inner) = line.strip_prefix('<') else {
+        return line;
+    };
+    let Some((digits, rest)) = inner.split_once('>') else {
+        return line;
+    };
+    if digits.is_empty() || digits.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return line;
+    }
+    let Ok(priority) = digits.parse::<usize>() else {
+        return line;
+    };
+    if priority > 191 {
+        return line;
+    }
+
+    fields.insert("priority", priority as i64);
+    fields.insert("facility", FACILITIES[priority / 8]);
+    fields.insert("severity", SEVERITIES[priority % 8]);
+    rest
+}
+
+/// Parse the remainder of an RFC 3164 line, keeping anything unrecognised as the message
+fn parse_rfc3164(rest: &str, fields: &mut Fields) -> Option<()> {
+    let mut rest = rest.trim_start();
+
+    if let Some(timestamp) = BSD_TIMESTAMP.find(rest) {
+        fields.insert(
+            "timestamp",
+            timestamp.as_str().trim_end().trim_end_matches(':'),
+        );
+        rest = &rest[timestamp.end()..];
+
+        // The hostname is the next word, unless that word is already the tag
+        if !BSD_TAG.is_match(rest)
+            && let Some((hostname, after)) = rest.split_once(' ')
+            && !hostname.is_empty()
+        {
+            fields.insert("hostname", hostname);
+            rest = after.trim_start();
+        }
+    } else if fields.is_empty() {
+        return None;
+    }
+
+    if let Some(captures) = BSD_TAG.captures(rest) {
+        fields.insert("app_name", &captures[1]);
+        if let Some(pid) = captures.get(2).filter(|pid| !pid.is_empty()) {
+            fields.insert("procid", pid.as_str());
+        }
+        rest = &rest[captures.get(0).map_or(0, |m| m.end())..];
+    }
+
+    fields.insert("message", rest.trim_start());
+    Some(())
+}
+
+/// Parse the remainder of an RFC 5424 line, failing if the header is malformed
+fn parse_rfc5424(rest: &str, fields: &mut Fields) -> Option<()> {
+    let mut parsed = Fields::new();
+    let mut rest = rest.trim_start();
+
+    let version = next_token(&mut rest)?.parse::<i64>().ok()?;
+    parsed.insert("version", version);
+    for name in ["timestamp", "hostname", "app_name", "procid", "msgid"] {
+        let token = next_token(&mut rest)?;
+        if token != "-" {
+            parsed.insert(name, token);
+        }
+    }
+
+    if let Some(after) = rest.strip_prefix('-') {
+        rest = after;
+    } else if rest.starts_with('[') {
+        // Malformed structured data is left in the message rather than dropped
+        if let Some((data, after)) = parse_structured_data(rest) {
+            parsed.insert("structured_data", FieldValue::Map(data));
+            rest = after;
+        }
+    }
+
+    let message = rest.strip_prefix(' ').unwrap_or(rest);
+    parsed.insert(
+        "message",
+        message.strip_prefix('\u{feff}').unwrap_or(message),
+    );
+
+    fields.extend(parsed);
+    Some(())
+}
+
+/// Take the next space separated token from the input
+fn next_token<'a>(input: &mut &'a str) -> Option<&'a str> {
+    let trimmed = input.trim_start_matches(' ');
+    if trimmed.is_empty() {
+        return None;
+    }
+    let (token, rest) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+    *input = rest;
+    Some(token)
+}
+
+/// Parse one or more `[id name="value" ...]` elements, returning them and the remaining input
+fn parse_structured_data(input: &str) -> Option<(Fields, &str)> {
+    let mut data = Fields::new();
+    let mut rest = input;
+
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = Fields::new();
+        rest = &element[id_end..];
+
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if let Some(after) = rest.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+            let (name, after) = rest.split_once("=\"")?;
+            if name.is_empty() || name.contains([' ', ']']) {
+                return None;
+            }
+            let (value, after) = parse_param_value(after)?;
+            params.insert(name, value);
+            rest = after;
+        }
+
+        data.insert(id, FieldValue::Map(params));
+    }
+
+    Some((data, rest))
+}
+
+/// Parse a quoted parameter value up to its closing quote, unescaping `\"`, `\\` and `\]`
+fn parse_param_value(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &input[index + 1..])),
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('"' | '\\' | ']'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => value.push('\\'),
+            },
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc3164_full_line() {
+        let fields = SyslogParser::new()
+            .parse("<34>Oct 11 22:14:15 mymachine su[230]: 'su root' failed for lonvick")
+            .unwrap();
+
+        assert_eq!(fields.get("priority"), Some(&FieldValue::Int(34)));
+        assert_eq!(fields.get_str("facility"), Some("auth"));
+        assert_eq!(fields.get_str("severity"), Some("crit"));
+        assert_eq!(fields.get_str("timestamp"), Some("Oct 11 22:14:15"));
+        assert_eq!(fields.get_str("hostname"), Some("mymachine"));
+        assert_eq!(fields.get_str("app_name"), Some("su"));
+        assert_eq!(fields.get_str("procid"), Some("230"));
+        assert_eq!(
+            fields.get_str("message"),
+            Some("'su root' failed for lonvick")
+        );
+    }
+
+    #[test]
+    fn test_rfc3164_file_line_without_priority() {
+        let fields = SyslogParser::new()
+            .parse("Oct  1 06:25:01 host CRON[1234]: (root) CMD (test -x /usr/sbin/anacron)")
+            .unwrap();
+
+        assert!(!fields.contains_key("priority"));
+        assert_eq!(fields.get_str("timestamp"), Some("Oct  1 06:25:01"));
+        assert_eq!(fields.get_str("hostname"), Some("host"));
+        assert_eq!(fields.get_str("app_name"), Some("CRON"));
+        assert_eq!(
+            fields.get_str("message"),
+            Some("(root) CMD (test -x /usr/sbin/anacron)")
+        );
+    }
+
+    #[test]
+    fn test_rfc3164_non_conforming_variants() {
+        let parser = SyslogParser::rfc3164();
+
+        // No hostname, tag without pid
+        let fields = parser
+            .parse("<13>Oct 11 22:14:15 kernel: eth0 link up")
+            .unwrap();
+        assert!(!fields.contains_key("hostname"));
+        assert_eq!(fields.get_str("app_name"), Some("kernel"));
+        assert_eq!(fields.get_str("message"), Some("eth0 link up"));
+
+        // ISO timestamp as written by rsyslog
+        let fields = parser
+            .parse("2024-03-01T10:00:00.123+01:00 web nginx[9]: started")
+            .unwrap();
+        assert_eq!(
+            fields.get_str("timestamp"),
+            Some("2024-03-01T10:00:00.123+01:00")
+        );
+        assert_eq!(fields.get_str("hostname"), Some("web"));
+
+        // Priority with nothing else recognisable
+        let fields = parser.parse("<14>just a message").unwrap();
+        assert_eq!(fields.get_str("severity"), Some("info"));
+        assert_eq!(fields.get_str("message"), Some("just a message"));
+
+        // Neither priority nor timestamp
+        assert!(parser.parse("plain application output").is_none());
+        assert!(parser.parse("<999>bad priority").is_none());
+    }
+
+    #[test]
+    fn test_rfc5424_full_line() {
+        let line = r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application" eventID="1011"][examplePriority@32473 class="high"] An application event log entry"#;
+        let fields = SyslogParser::new().parse(line).unwrap();
+
+        assert_eq!(fields.get("version"), Some(&FieldValue::Int(1)));
+        assert_eq!(fields.get_str("facility"), Some("local4"));
+        assert_eq!(fields.get_str("severity"), Some("notice"));
+        assert_eq!(
+            fields.get_str("timestamp"),
+            Some("2003-10-11T22:14:15.003Z")
+        );
+        assert_eq!(fields.get_str("hostname"), Some("mymachine.example.com"));
+        assert_eq!(fields.get_str("app_name"), Some("evntslog"));
+        assert!(!fields.contains_key("procid"));
+        assert_eq!(fields.get_str("msgid"), Some("ID47"));
+        assert_eq!(
+            fields.get_str("message"),
+            Some("An application event log entry")
+        );
+
+        let Some(FieldValue::Map(data)) = fields.get("structured_data") else {
+            panic!("Expected structured data");
+        };
+        assert_eq!(data.len(), 2);
+        let Some(FieldValue::Map(example)) = data.get("exampleSDID@32473") else {
+            panic!("Expected SD element");
+        };
+        assert_eq!(example.get_str("eventID"), Some("1011"));
+    }
+
+    #[test]
+    fn test_rfc5424_nil_values_and_bom() {
+        let fields = SyslogParser::rfc5424()
+            .parse("<34>1 - - - - - - \u{feff}hello")
+            .unwrap();
+
+        assert!(!fields.contains_key("timestamp"));
+        assert!(!fields.contains_key("hostname"));
+        assert!(!fields.contains_key("structured_data"));
+        assert_eq!(fields.get_str("message"), Some("hello"));
+    }
+
+    #[test]
+    fn test_rfc5424_structured_data_escapes() {
+        let line = r#"<34>1 2024-01-01T00:00:00Z host app 1 - [meta note="say \"hi\" [x\]" path="C:\\tmp"]"#;
+        let fields = SyslogParser::rfc5424().parse(line).unwrap();
+
+        let Some(FieldValue::Map(data)) = fields.get("structured_data") else {
+            panic!("Expected structured data");
+        };
+        let Some(FieldValue::Map(meta)) = data.get("meta") else {
+            panic!("Expected SD element");
+        };
+        assert_eq!(meta.get_str("note"), Some(r#"say "hi" [x]"#));
+        assert_eq!(meta.get_str("path"), Some(r"C:\tmp"));
+        assert_eq!(fields.get_str("message"), Some(""));
+    }
+
+    #[test]
+    fn test_rfc5424_malformed_structured_data_kept_in_message() {
+        let fields = SyslogParser::rfc5424()
+            .parse(r#"<34>1 2024-01-01T00:00:00Z host app - - [broken "x"] text"#)
+            .unwrap();
+
+        assert!(!fields.contains_key("structured_data"));
+        assert_eq!(fields.get_str("message"), Some(r#"[broken "x"] text"#));
+    }
+
+    #[test]
+    fn test_rfc5424_truncated_header() {
+        assert!(
+            SyslogParser::rfc5424()
+                .parse("<34>1 2024-01-01T00:00:00Z host")
+                .is_none()
+        );
+        // Auto falls back to RFC 3164 for the same line
+        let fields = SyslogParser::new()
+            .parse("<34>1 2024-01-01T00:00:00Z host")
+            .unwrap();
+        assert!(!fields.contains_key("version"));
+        assert_eq!(fields.get_str("severity"), Some("crit"));
+    }
+}
@@ -0,0 +1,285 @@
+//! User supplied processing stages that run on records in the reader task.
+
+use crate::filter::RecordFilter;
+#[cfg(feature = "json")]
+use crate::json::JsonParser;
+use crate::parser::{
+    AccessLogParser, AuditParser, GrokParser, LogfmtParser, Parser, RegexParser, SyslogParser,
+};
+use crate::record::Record;
+use crate::redact::RedactionConfig;
+use futures::future::BoxFuture;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Transforms records one at a time.
+///
+/// A processor can map a record by returning a changed copy, drop it by
+/// returning nothing, split it by returning several records, or enrich it by
+/// adding fields. Closures taking a [`Record`] and returning `Vec<Record>`
+/// can be used as processors directly.
+pub trait LineProcessor: Send + Sync {
+    /// Processes a record, returning the records to pass on in its place.
+    fn process(&self, record: Record) -> Vec<Record>;
+}
+
+impl<F> LineProcessor for F
+where
+    F: Fn(Record) -> Vec<Record> + Send + Sync,
+{
+    fn process(&self, record: Record) -> Vec<Record> {
+        self(record)
+    }
+}
+
+/// Like [`LineProcessor`], but awaits while processing, for example to look
+/// something up over the network.
+///
+/// Closures taking a [`Record`] and returning a future of `Vec<Record>` can
+/// be used as async processors directly.
+pub trait AsyncLineProcessor: Send + Sync {
+    /// Processes a record, returning the records to pass on in its place.
+    fn process(&self, record: Record) -> BoxFuture<'_, Vec<Record>>;
+}
+
+impl<F, Fut> AsyncLineProcessor for F
+where
+    F: Fn(Record) -> Fut + Send + Sync,
+    Fut: Future<Output = Vec<Record>> + Send + 'static,
+{
+    fn process(&self, record: Record) -> BoxFuture<'_, Vec<Record>> {
+        Box::pin(self(record))
+    }
+}
+
+/// Drops rejected records. Context lines are only emitted when the filter is
+/// set with [`StreamConfig::filter`](crate::StreamConfig::filter).
+impl LineProcessor for RecordFilter {
+    fn process(&self, record: Record) -> Vec<Record> {
+        if self.keeps(&record) {
+            vec![record]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Redacts the record. Matches are only counted in the stream statistics
+/// when redaction is set with [`StreamConfig::redaction`](crate::StreamConfig::redaction).
+impl LineProcessor for RedactionConfig {
+    fn process(&self, mut record: Record) -> Vec<Record> {
+        self.redact(&mut record, &mut BTreeMap::new());
+        vec![record]
+    }
+}
+
+/// Add the fields parsed from the record text, passing records that do not parse on unchanged
+fn parse_fields(parser: &impl Parser, mut record: Record) -> Vec<Record> {
+    if let Some(fields) = parser.parse(&record.text) {
+        record.fields.extend(fields);
+    }
+    vec![record]
+}
+
+impl LineProcessor for AccessLogParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+impl LineProcessor for AuditParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+impl LineProcessor for GrokParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+#[cfg(feature = "json")]
+impl LineProcessor for JsonParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+impl LineProcessor for LogfmtParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+impl LineProcessor for RegexParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+impl LineProcessor for SyslogParser {
+    fn process(&self, record: Record) -> Vec<Record> {
+        parse_fields(self, record)
+    }
+}
+
+/// A stage of the pipeline.
+#[derive(Clone)]
+enum Stage {
+    Sync(Arc<dyn LineProcessor>),
+    Async(Arc<dyn AsyncLineProcessor>),
+}
+
+/// An ordered list of processors, each receiving the output of the one before.
+///
+/// The pipeline is configured with [`StreamConfig::pipeline`](crate::StreamConfig::pipeline)
+/// and runs in the reader task after parsing, filtering and redaction, so
+/// processors never see the values redaction removes and records only reach
+/// the stream once every stage has run. Add a [`RedactionConfig`] as the last
+/// processor to also redact values the processors add.
+///
+/// The built-in parsers, [`RecordFilter`] and [`RedactionConfig`] are
+/// processors too. Dedup and sampling are not: they keep state across
+/// batches and emit their summaries on a timer when no records arrive, which
+/// a processor only called for each record cannot do, so they are configured
+/// with [`StreamConfig::dedup`](crate::StreamConfig::dedup) and
+/// [`StreamConfig::sampling`](crate::StreamConfig::sampling) instead.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{LogfmtParser, Pipeline, Record, RecordFilter, StreamConfig};
+///
+/// # fn main() -> log_reader::Result<()> {
+/// let pipeline = Pipeline::new()
+///     .processor(LogfmtParser::new())
+///     .processor(RecordFilter::new().exclude(["healthcheck"])?)
+///     .processor(|mut record: Record| {
+///         record.fields.insert("host", "web-1");
+///         vec![record]
+///     })
+///     .async_processor(|record: Record| async move { vec![record] });
+/// let config = StreamConfig::new().pipeline(pipeline);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline that passes records through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a processor to the end of the pipeline.
+    pub fn processor(mut self, processor: impl LineProcessor + 'static) -> Self {
+        self.stages.push(Stage::Sync(Arc::new(processor)));
+        self
+    }
+
+    /// Adds an async processor to the end of the pipeline.
+    pub fn async_processor(mut self, processor: impl AsyncLineProcessor + 'static) -> Self {
+        self.stages.push(Stage::Async(Arc::new(processor)));
+        self
+    }
+
+    /// Returns the number of processors.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns true if the pipeline has no processors.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Runs every record through each processor in order.
+    pub async fn run(&self, mut records: Vec<Record>) -> Vec<Record> {
+        for stage in &self.stages {
+            let mut output = Vec::with_capacity(records.len());
+            for record in records {
+                match stage {
+                    Stage::Sync(processor) => output.extend(processor.process(record)),
+                    Stage::Async(processor) => output.extend(processor.process(record).await),
+                }
+            }
+            records = output;
+        }
+        records
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("processors", &self.stages.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(records: &[Record]) -> Vec<&str> {
+        records.iter().map(|record| record.text.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_map_drop_split_and_enrich() {
+        let pipeline = Pipeline::new()
+            .processor(|record: Record| {
+                record
+                    .text
+                    .split(';')
+                    .map(|part| Record::new(part.trim()))
+                    .collect()
+            })
+            .processor(|record: Record| match record.text.as_str() {
+                "skip" => Vec::new(),
+                _ => vec![Record::new(record.text.to_uppercase())],
+            })
+            .async_processor(|mut record: Record| async move {
+                record.fields.insert("length", record.text.len() as i64);
+                vec![record]
+            });
+
+        let records = pipeline
+            .run(vec![Record::new("a; skip; b"), Record::new("c")])
+            .await;
+        assert_eq!(texts(&records), vec!["A", "B", "C"]);
+        assert_eq!(records[2].fields.get("length").unwrap().as_i64(), Some(1));
+        assert_eq!(pipeline.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_builtin_processors() {
+        let pipeline = Pipeline::new()
+            .processor(LogfmtParser::new())
+            .processor(RecordFilter::new().include(["user"]).unwrap())
+            .processor(RedactionConfig::new());
+
+        let records = pipeline
+            .run(vec![Record::new("user=a@b.io"), Record::new("tick")])
+            .await;
+        assert_eq!(texts(&records), vec!["user=[REDACTED:email]"]);
+        assert_eq!(records[0].fields.get_str("user"), Some("[REDACTED:email]"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_pipeline() {
+        let pipeline = Pipeline::new();
+        assert!(pipeline.is_empty());
+        assert_eq!(
+            texts(&pipeline.run(vec![Record::new("x")]).await),
+            vec!["x"]
+        );
+        assert_eq!(format!("{:?}", pipeline), "Pipeline { processors: 0 }");
+    }
+}
@@ -1,17 +1,108 @@
 //! File reading utilities for log processing.
 
+use crate::audit::AuditAssembler;
+use crate::config::StreamConfig;
+use crate::container::ContainerDecoder;
+use crate::dedup::Deduplicator;
+use crate::delimited::DelimitedDecoder;
 use crate::error::Result;
+use crate::filter::{ContextWindow, MatchContext};
+use crate::multiline::MultilineAggregator;
+use crate::record::{FieldValue, Record};
+use crate::sampling::Sampler;
+use crate::stats::StreamStats;
+use crate::timestamp::TimestampConfig;
+use chrono::{DateTime, FixedOffset, Utc};
+use std::borrow::Cow;
 use std::path::Path;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How much of the start of the file is read to find a header row.
+const HEADER_READ_LIMIT: u64 = 64 * 1024;
+
+/// Channel used to send batches of records to the stream.
+pub(crate) type RecordSender = mpsc::UnboundedSender<Result<Vec<Record>>>;
+
+/// State carried between reads of the same file.
+#[derive(Debug, Default)]
+pub(crate) struct ReaderState {
+    /// Byte offset up to which the file has been read.
+    pub(crate) position: u64,
+    /// Counters for the content read so far.
+    pub(crate) stats: StreamStats,
+    /// Unterminated content held back until the next read.
+    pub(crate) partial: String,
+    /// Unwraps container runtime lines when configured.
+    pub(crate) container: Option<ContainerDecoder>,
+    /// Splits delimited rows into fields when configured.
+    pub(crate) delimited: Option<DelimitedDecoder>,
+    /// Groups audit log lines into events when configured.
+    pub(crate) audit: Option<AuditAssembler>,
+    /// Joins lines into multiline records when configured.
+    pub(crate) multiline: Option<MultilineAggregator>,
+    /// Holds rejected records that may be emitted as context of a match.
+    pub(crate) context: Option<ContextWindow>,
+    /// Collapses repeated records when configured.
+    pub(crate) dedup: Option<Deduplicator>,
+    /// Samples and rate limits records when configured.
+    pub(crate) sampler: Option<Sampler>,
+}
+
+impl ReaderState {
+    /// Create the initial state for reading with the given configuration.
+    pub(crate) fn new(config: &StreamConfig) -> Self {
+        Self {
+            container: config.container_format.map(ContainerDecoder::new),
+            delimited: config.delimited.clone().map(DelimitedDecoder::new),
+            audit: config.audit_events.clone().map(AuditAssembler::new),
+            multiline: config.multiline.clone().map(MultilineAggregator::new),
+            context: config.filter.as_ref().and_then(ContextWindow::new),
+            dedup: config.dedup.clone().map(Deduplicator::new),
+            sampler: config.sampling.clone().map(Sampler::new),
+            ..Default::default()
+        }
+    }
+
+    /// Returns when held back records should be emitted if nothing else arrives.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        let container = self
+            .container
+            .as_ref()
+            .and_then(ContainerDecoder::flush_deadline);
+        let audit = self.audit.as_ref().and_then(AuditAssembler::flush_deadline);
+        let multiline = self
+            .multiline
+            .as_ref()
+            .and_then(MultilineAggregator::flush_deadline);
+        let dedup = self.dedup.as_ref().and_then(Deduplicator::flush_deadline);
+        let sampler = self.sampler.as_ref().and_then(Sampler::flush_deadline);
+        container
+            .into_iter()
+            .chain(audit)
+            .chain(multiline)
+            .chain(dedup)
+            .chain(sampler)
+            .min()
+    }
+
+    /// Consume the state and return the final statistics.
+    pub(crate) fn into_stats(self) -> StreamStats {
+        StreamStats {
+            position: self.position,
+            ..self.stats
+        }
+    }
+}
 
 /// Read content from file and send lines through the channel
 pub(crate) async fn read_file_content(
     file_path: &Path,
-    last_position: &mut u64,
-    separator: &str,
-    tx: &mpsc::UnboundedSender<Result<Vec<String>>>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+    tx: &RecordSender,
 ) -> Result<()> {
     if !file_path.exists() {
         return Ok(());
@@ -21,19 +112,35 @@ pub(crate) async fn read_file_content(
     let metadata = file.metadata().await?;
     let current_size = metadata.len();
 
-    // Handle file truncation
-    if detect_file_truncation(current_size, *last_position) {
-        *last_position = 0;
+    // Handle file truncation, anything held back belongs to the old content
+    if detect_file_truncation(current_size, state.position) {
+        flush_pending_records(state, config, tx).await;
+        state.position = 0;
+        state.stats.truncations += 1;
+        if let Some(decoder) = &mut state.delimited {
+            decoder.reset_header();
+        }
+        if let Some(window) = &mut state.context {
+            state.stats.records_filtered += window.clear() as u64;
+        }
+    }
+
+    // Started past the header row, so read it from the start of the file
+    if state.position > 0
+        && let Some(decoder) = &mut state.delimited
+        && decoder.needs_header()
+    {
+        decoder.read_header(read_leading_records(&mut file, config).await?);
     }
 
     // Check if there's new content to read
-    let bytes_to_read = match calculate_bytes_to_read(current_size, *last_position) {
+    let bytes_to_read = match calculate_bytes_to_read(current_size, state.position) {
         Some(bytes) => bytes,
         None => return Ok(()), // Nothing new to read
     };
 
     // Seek to last known position
-    file.seek(std::io::SeekFrom::Start(*last_position)).await?;
+    file.seek(std::io::SeekFrom::Start(state.position)).await?;
 
     // Read new content
     let mut new_content = String::new();
@@ -42,34 +149,396 @@ pub(crate) async fn read_file_content(
         .await?;
 
     // Update position
-    *last_position = current_size;
+    state.position = current_size;
+    state.stats.bytes_read += bytes_to_read;
 
     // Split by separator and collect all parts into a Vec
-    let parts = split_and_filter_content(&new_content, separator);
+    let parts = if config.buffer_partial_records {
+        let mut buffer = std::mem::take(&mut state.partial);
+        buffer.push_str(&new_content);
+
+        let (parts, rest) = config.delimiter.split(&buffer);
+        state.partial = rest.to_string();
+        filter_records(parts, config)
+    } else {
+        split_and_filter_content(&new_content, config)
+    };
+
+    let records = process_records(parts, state, config).await;
+    send_records(records, state, tx);
+
+    Ok(())
+}
+
+/// Emit everything held back by partial record buffering, container decoding, audit event assembly and multiline aggregation
+pub(crate) async fn flush_pending_records(
+    state: &mut ReaderState,
+    config: &StreamConfig,
+    tx: &RecordSender,
+) {
+    let partial = std::mem::take(&mut state.partial);
+    let mut records = decode_records(filter_records([partial.as_str()], config), state, config);
+
+    if let Some(decoder) = &mut state.container {
+        records.extend(clean_decoded(decoder.flush(), config));
+    }
+    let mut records = split_rows(records, state);
+    if let Some(decoder) = &mut state.delimited {
+        records.extend(decoder.flush());
+    }
+    let mut records = assemble_events(records, state);
+    if let Some(assembler) = &mut state.audit {
+        records.extend(assembler.flush());
+    }
+    let mut records = aggregate_records(records, state);
+    if let Some(aggregator) = &mut state.multiline {
+        records.extend(aggregator.flush());
+    }
+
+    let records = parse_records(records, config);
+    send_records(
+        finish_records(records, state, config, true).await,
+        state,
+        tx,
+    );
+}
 
-    // Send the entire Vec if it's not empty
+/// Emit held back records whose flush timeout has passed
+pub(crate) async fn flush_expired_records(
+    state: &mut ReaderState,
+    config: &StreamConfig,
+    tx: &RecordSender,
+) {
+    let now = Instant::now();
+    if state.flush_deadline().is_none_or(|deadline| deadline > now) {
+        return;
+    }
+
+    let lines = match &mut state.container {
+        Some(decoder) => clean_decoded(decoder.flush_expired(now), config),
+        None => Vec::new(),
+    };
+    let mut events = if lines.is_empty() {
+        lines
+    } else {
+        assemble_events(split_rows(lines, state), state)
+    };
+    if let Some(assembler) = &mut state.audit {
+        events.extend(assembler.flush_expired(now));
+    }
+    let multiline_expired = state
+        .multiline
+        .as_ref()
+        .and_then(MultilineAggregator::flush_deadline)
+        .is_some_and(|deadline| deadline <= now);
+
+    // Pushing nothing would restart the multiline timeout
+    let mut records = if events.is_empty() {
+        events
+    } else {
+        aggregate_records(events, state)
+    };
+    if multiline_expired && let Some(aggregator) = &mut state.multiline {
+        records.extend(aggregator.flush());
+    }
+    let records = parse_records(records, config);
+    send_records(
+        finish_records(records, state, config, false).await,
+        state,
+        tx,
+    );
+}
+
+/// Run split records through the stages that follow splitting
+async fn process_records(
+    parts: Vec<String>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+) -> Vec<Record> {
+    let records = decode_records(parts, state, config);
+    let records = split_rows(records, state);
+    let records = assemble_events(records, state);
+    let records = aggregate_records(records, state);
+    let records = parse_records(records, config);
+    finish_records(records, state, config, false).await
+}
+
+/// Run parsed records through filtering, deduplication, sampling, redaction
+/// and the processor pipeline, emitting any held back repeats and drop
+/// summaries when `flush` is set
+async fn finish_records(
+    records: Vec<Record>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+    flush: bool,
+) -> Vec<Record> {
+    let records = drop_filtered(records, state, config);
+    let records = reduce_records(records, state, flush);
+    // Processors may send records elsewhere, so they only ever see redacted values
+    let records = redact_records(records, state, config);
+    match &config.pipeline {
+        Some(pipeline) => pipeline.run(records).await,
+        None => records,
+    }
+}
+
+/// Turn text into records, unwrapping container runtime lines when configured
+fn decode_records(
+    parts: Vec<String>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+) -> Vec<Record> {
+    let records = parts.into_iter().map(Record::new).collect();
+    match &mut state.container {
+        Some(decoder) => clean_decoded(decoder.decode_all(records), config),
+        None => records,
+    }
+}
+
+/// Handle ANSI escapes and empty records in lines unwrapped from the container format
+fn clean_decoded(mut records: Vec<Record>, config: &StreamConfig) -> Vec<Record> {
+    // Runtimes such as Docker escape the sequences, so they only appear once decoded
+    for record in &mut records {
+        if let Cow::Owned(text) = config.ansi.clean(&record.text) {
+            record.text = text;
+        }
+    }
+    records.retain(|record| config.empty_records.keeps(&record.text));
+    records
+}
+
+/// Split delimited rows into fields when configured
+fn split_rows(records: Vec<Record>, state: &mut ReaderState) -> Vec<Record> {
+    match &mut state.delimited {
+        Some(decoder) => decoder.push_all(records),
+        None => records,
+    }
+}
+
+/// Group audit log lines into events when configured
+fn assemble_events(records: Vec<Record>, state: &mut ReaderState) -> Vec<Record> {
+    match &mut state.audit {
+        Some(assembler) => assembler.push_all(records),
+        None => records,
+    }
+}
+
+/// Join records with the multiline aggregator when configured
+fn aggregate_records(records: Vec<Record>, state: &mut ReaderState) -> Vec<Record> {
+    match &mut state.multiline {
+        Some(aggregator) => aggregator.push_all(records),
+        None => records,
+    }
+}
+
+/// Extract fields, the event time and the level from records as configured
+fn parse_records(records: impl IntoIterator<Item = Record>, config: &StreamConfig) -> Vec<Record> {
+    let now = Utc::now();
+    records
+        .into_iter()
+        .map(|mut record| {
+            if let Some(fields) = config.parser.as_ref().and_then(|p| p.parse(&record.text)) {
+                record.fields.extend(fields);
+            }
+            if let Some(timestamps) = &config.timestamps {
+                record.timestamp = timestamps.extract(&record, now);
+            } else if record.timestamp.is_none() {
+                record.timestamp = parsed_timestamp(&record);
+            }
+            if let Some(levels) = &config.levels {
+                record.level = levels.detect(&record);
+            }
+            record
+        })
+        .collect()
+}
+
+/// A time the parser already typed as a timestamp in one of the usual time fields
+fn parsed_timestamp(record: &Record) -> Option<DateTime<FixedOffset>> {
+    TimestampConfig::DEFAULT_FIELDS
+        .iter()
+        .find_map(|name| match record.fields.get(name)? {
+            FieldValue::Timestamp(timestamp) => Some(*timestamp),
+            _ => None,
+        })
+}
+
+/// Drop records below the minimum level or rejected by the filter and count
+/// them, keeping rejected records around matches when context is configured
+fn drop_filtered(
+    mut records: Vec<Record>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+) -> Vec<Record> {
+    if config.levels.is_none() && config.filter.is_none() {
+        return records;
+    }
+
+    let keeps = |record: &Record| {
+        config
+            .levels
+            .as_ref()
+            .is_none_or(|levels| levels.keeps(record.level))
+            && config
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.keeps(record))
+    };
+
+    if let Some(window) = &mut state.context {
+        let mut kept = Vec::with_capacity(records.len());
+        for record in records {
+            let matched = keeps(&record);
+            state.stats.records_filtered += window.push(record, matched, &mut kept) as u64;
+        }
+        return kept;
+    }
+
+    let before = records.len();
+    records.retain(keeps);
+    state.stats.records_filtered += (before - records.len()) as u64;
+    records
+}
+
+/// Collapse repeated records and then sample and rate limit the rest when
+/// configured, counting the ones dropped. Context lines and the summaries of
+/// collapsed repeats skip both, so they are never lost
+fn reduce_records(records: Vec<Record>, state: &mut ReaderState, flush: bool) -> Vec<Record> {
+    let ReaderState {
+        dedup,
+        sampler,
+        stats,
+        ..
+    } = state;
+    if dedup.is_none() && sampler.is_none() {
+        return records;
+    }
+
+    let now = Instant::now();
+    let mut output = Vec::with_capacity(records.len());
+    if let Some(sampler) = sampler.as_mut() {
+        output.extend(sampler.push_all(Vec::new(), now));
+    }
+    if let Some(dedup) = dedup.as_mut() {
+        output.extend(dedup.flush_expired(now));
+    }
+
+    let mut sample = |record: Record, output: &mut Vec<Record>| match sampler.as_mut() {
+        Some(sampler) => output.extend(sampler.push_all(vec![record], now)),
+        None => output.push(record),
+    };
+    for record in records {
+        match dedup.as_mut() {
+            // A context line ends the run of repeats before it
+            Some(dedup) if is_context(&record) => {
+                output.extend(dedup.flush());
+                output.push(record);
+            }
+            None if is_context(&record) => output.push(record),
+            Some(dedup) => {
+                let (summary, record) = dedup.push(record, now);
+                output.extend(summary);
+                if let Some(record) = record {
+                    sample(record, &mut output);
+                }
+            }
+            None => sample(record, &mut output),
+        }
+    }
+
+    if let Some(dedup) = dedup.as_mut() {
+        if flush {
+            output.extend(dedup.flush());
+        }
+        stats.records_deduplicated = dedup.suppressed();
+    }
+    if let Some(sampler) = sampler.as_mut() {
+        if flush {
+            output.extend(sampler.flush());
+        }
+        stats.records_sampled = sampler.dropped();
+    }
+    output
+}
+
+/// Context lines and separators show where a match sits in the file, so they
+/// are never collapsed or sampled away
+fn is_context(record: &Record) -> bool {
+    matches!(
+        record.context,
+        Some(MatchContext::Before | MatchContext::After | MatchContext::Separator)
+    )
+}
+
+/// Mask sensitive values in the records that are about to be sent and count them
+fn redact_records(
+    mut records: Vec<Record>,
+    state: &mut ReaderState,
+    config: &StreamConfig,
+) -> Vec<Record> {
+    if let Some(redaction) = &config.redaction {
+        for record in &mut records {
+            redaction.redact(record, &mut state.stats.redactions);
+        }
+    }
+    records
+}
+
+/// Send the entire Vec if it's not empty and count it
+fn send_records(parts: Vec<Record>, state: &mut ReaderState, tx: &RecordSender) {
     if !parts.is_empty() {
+        state.stats.lines_emitted += parts.len() as u64;
+        state.stats.batches_emitted += 1;
         let _ = tx.send(Ok(parts));
     }
+}
 
-    Ok(())
+/// Split content by delimiter and filter out parts according to the empty record policy
+fn split_and_filter_content(content: &str, config: &StreamConfig) -> Vec<String> {
+    let (mut parts, rest) = config.delimiter.split(content);
+
+    // Without buffering the unterminated tail is a record of its own
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+
+    filter_records(parts, config)
 }
 
-/// Split content by separator and filter out empty/whitespace-only parts
-fn split_and_filter_content(content: &str, separator: &str) -> Vec<String> {
-    content
-        .split(separator)
-        .filter_map(|part| {
-            let trimmed = part.trim();
-            if trimmed.is_empty() {
-                None
+/// Trim records, handle ANSI escapes and drop the ones rejected by the empty record policy
+fn filter_records<'a>(
+    parts: impl IntoIterator<Item = &'a str>,
+    config: &StreamConfig,
+) -> Vec<String> {
+    parts
+        .into_iter()
+        .map(|part| config.delimiter.trim_record(part))
+        .map(|part| {
+            if config.trim_carriage_return {
+                part.strip_suffix('\r').unwrap_or(part)
             } else {
-                Some(part.to_string())
+                part
             }
         })
+        .map(|part| config.ansi.clean(part))
+        .filter(|part| config.empty_records.keeps(part))
+        .map(String::from)
         .collect()
 }
 
+/// Read the records at the start of the file, enough to find a header row
+async fn read_leading_records(file: &mut File, config: &StreamConfig) -> Result<Vec<String>> {
+    let mut buffer = Vec::new();
+    file.seek(std::io::SeekFrom::Start(0)).await?;
+    file.take(HEADER_READ_LIMIT)
+        .read_to_end(&mut buffer)
+        .await?;
+
+    let content = String::from_utf8_lossy(&buffer);
+    let (parts, _) = config.delimiter.split(&content);
+    Ok(filter_records(parts, config))
+}
+
 /// Detect if the file was truncated by comparing current size with last position
 fn detect_file_truncation(current_size: u64, last_position: u64) -> bool {
     current_size < last_position
@@ -87,20 +556,40 @@ fn calculate_bytes_to_read(current_size: u64, last_position: u64) -> Option<u64>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ansi::AnsiMode;
+    use crate::audit::AuditConfig;
+    use crate::config::EmptyRecordPolicy;
+    use crate::container::ContainerFormat;
+    use crate::dedup::DedupConfig;
+    use crate::delimited::DelimitedFormat;
+    use crate::delimiter::Delimiter;
+    use crate::filter::RecordFilter;
+    use crate::level::{Level, LevelConfig};
+    use crate::multiline::MultilineConfig;
+    use crate::parser::{AccessLogParser, LogfmtParser, Parser, RegexParser};
+    use crate::processor::Pipeline;
+    use crate::redact::RedactionConfig;
+    use crate::sampling::SamplingConfig;
     use std::path::PathBuf;
+    use std::time::Duration;
     use tokio::fs;
     use tokio::sync::mpsc;
 
-    /// Helper function to collect all messages from the receiver
+    /// Helper function to build a config with a custom separator
+    fn with_separator(separator: &str) -> StreamConfig {
+        StreamConfig::new().separator(separator)
+    }
+
+    /// Helper function to collect the text of all messages from the receiver
     async fn collect_messages(
-        mut rx: mpsc::UnboundedReceiver<Result<Vec<String>>>,
+        mut rx: mpsc::UnboundedReceiver<Result<Vec<Record>>>,
     ) -> Vec<Vec<String>> {
         let mut messages = Vec::new();
 
         // Use try_recv to avoid blocking - all messages should be available immediately
         while let Ok(result) = rx.try_recv() {
             match result {
-                Ok(content) => messages.push(content),
+                Ok(content) => messages.push(content.into_iter().map(String::from).collect()),
                 Err(e) => panic!("Unexpected error: {}", e),
             }
         }
@@ -112,60 +601,907 @@ mod tests {
     #[test]
     fn test_split_and_filter_content_newline() {
         let content = "line1\nline2\nline3\n";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2", "line3"]);
     }
 
     #[test]
     fn test_split_and_filter_content_with_empty_lines() {
         let content = "line1\n\n\nline2\n  \n\nline3\n";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2", "line3"]);
     }
 
     #[test]
     fn test_split_and_filter_content_custom_separator() {
         let content = "data1|data2|data3|";
-        let result = split_and_filter_content(content, "|");
+        let result = split_and_filter_content(content, &with_separator("|"));
         assert_eq!(result, vec!["data1", "data2", "data3"]);
     }
 
     #[test]
     fn test_split_and_filter_content_multi_char_separator() {
         let content = "part1<<>>part2<<>>part3<<>>";
-        let result = split_and_filter_content(content, "<<>>");
+        let result = split_and_filter_content(content, &with_separator("<<>>"));
         assert_eq!(result, vec!["part1", "part2", "part3"]);
     }
 
     #[test]
     fn test_split_and_filter_content_no_separator() {
         let content = "single_line_content";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["single_line_content"]);
     }
 
     #[test]
     fn test_split_and_filter_content_empty_string() {
         let content = "";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, Vec::<String>::new());
     }
 
     #[test]
     fn test_split_and_filter_content_only_separators() {
         let content = "\n\n\n";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, Vec::<String>::new());
     }
 
     #[test]
     fn test_split_and_filter_content_whitespace_preservation() {
         let content = "  line1  \n  line2  \n";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         // Should preserve internal whitespace but filter empty lines
         assert_eq!(result, vec!["  line1  ", "  line2  "]);
     }
 
+    #[test]
+    fn test_split_and_filter_content_keep_all() {
+        let config = StreamConfig::new().empty_records(EmptyRecordPolicy::KeepAll);
+        let content = "record1\n\n  \nrecord2\n";
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["record1", "", "  ", "record2"]);
+    }
+
+    #[test]
+    fn test_split_and_filter_content_drop_empty_only() {
+        let config = StreamConfig::new().empty_records(EmptyRecordPolicy::DropEmpty);
+        let content = "record1\n\n  \nrecord2\n";
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["record1", "  ", "record2"]);
+    }
+
+    #[test]
+    fn test_split_and_filter_content_keep_all_empty_string() {
+        let config = StreamConfig::new().empty_records(EmptyRecordPolicy::KeepAll);
+        assert_eq!(split_and_filter_content("", &config), Vec::<String>::new());
+        assert_eq!(split_and_filter_content("\n", &config), vec![""]);
+    }
+
+    #[test]
+    fn test_split_and_filter_content_ansi() {
+        let content = "\x1b[32mINFO\x1b[0m ready\n\x1b[2K\n\x1b[31mERROR\r\n";
+
+        let config = StreamConfig::new().ansi(AnsiMode::Strip);
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["INFO ready", "ERROR"]);
+
+        let config = config.ansi(AnsiMode::Normalize);
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(
+            result,
+            vec!["\x1b[32mINFO\x1b[0m ready", "\x1b[31mERROR\x1b[0m"]
+        );
+    }
+
+    #[test]
+    fn test_split_and_filter_content_trim_carriage_return() {
+        let content = "line1\r\nline2\r\n\r\nline3";
+
+        let result = split_and_filter_content(content, &with_separator("\n"));
+        assert_eq!(result, vec!["line1\r", "line2\r", "line3"]);
+
+        let config = StreamConfig::new().trim_carriage_return(true);
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["line1", "line2", "line3"]);
+
+        let config = config.empty_records(EmptyRecordPolicy::DropEmpty);
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["line1", "line2", "line3"]);
+
+        let config = config.empty_records(EmptyRecordPolicy::KeepAll);
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(result, vec!["line1", "line2", "", "line3"]);
+    }
+
+    #[test]
+    fn test_split_and_filter_content_any_of() {
+        let config = StreamConfig::new().delimiter(Delimiter::any_of(["\r\n", "\n"]).unwrap());
+        let result = split_and_filter_content("line1\r\nline2\nline3\r\n", &config);
+        assert_eq!(result, vec!["line1", "line2", "line3"]);
+    }
+
+    #[test]
+    fn test_split_and_filter_content_boundary() {
+        let delimiter = Delimiter::boundary(r"(?m)^\d{4}-\d{2}-\d{2} ").unwrap();
+        let config = StreamConfig::new().delimiter(delimiter);
+        let content = "2024-01-01 first\n  detail\n2024-01-02 second\n";
+
+        let result = split_and_filter_content(content, &config);
+        assert_eq!(
+            result,
+            vec!["2024-01-01 first\n  detail", "2024-01-02 second"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partial_record_buffering_across_reads() {
+        let temp_file = "test_partial_records.tmp";
+        fs::write(temp_file, "line1\nline2\npart").await.unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().buffer_partial_records(true);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::default();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        assert_eq!(state.partial, "part");
+
+        // Complete the record and start another one
+        let mut content = fs::read_to_string(temp_file).await.unwrap();
+        content.push_str("ial\nnext");
+        fs::write(temp_file, content).await.unwrap();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        flush_pending_records(&mut state, &config, &tx).await;
+
+        let messages = collect_messages(rx).await;
+        assert_eq!(
+            messages,
+            vec![vec!["line1", "line2"], vec!["partial"], vec!["next"]]
+        );
+        assert!(state.partial.is_empty());
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_boundary_records_with_buffering() {
+        let temp_file = "test_boundary_records.tmp";
+        fs::write(temp_file, "[1] start\n  at frame\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .delimiter(Delimiter::boundary(r"(?m)^\[\d+\]").unwrap())
+            .buffer_partial_records(true);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::default();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        // The continuation arrives in a later write
+        let mut content = fs::read_to_string(temp_file).await.unwrap();
+        content.push_str("  at frame 2\n[2] next\n");
+        fs::write(temp_file, content).await.unwrap();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        flush_pending_records(&mut state, &config, &tx).await;
+
+        let messages = collect_messages(rx).await;
+        assert_eq!(
+            messages,
+            vec![
+                vec!["[1] start\n  at frame\n  at frame 2"],
+                vec!["[2] next"]
+            ]
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_truncation_flushes_partial_record() {
+        let temp_file = "test_truncation_partial.tmp";
+        fs::write(temp_file, "old line\nold partial").await.unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().buffer_partial_records(true);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::default();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        fs::write(temp_file, "new\n").await.unwrap();
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let messages = collect_messages(rx).await;
+        assert_eq!(
+            messages,
+            vec![vec!["old line"], vec!["old partial"], vec!["new"]]
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multiline_records_across_reads() {
+        let temp_file = "test_multiline_records.tmp";
+        fs::write(temp_file, "ERROR boom\n    at frame 1\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().multiline(MultilineConfig::continuation_whitespace());
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        assert!(state.flush_deadline().is_some());
+
+        let mut content = fs::read_to_string(temp_file).await.unwrap();
+        content.push_str("    at frame 2\nINFO done\n");
+        fs::write(temp_file, content).await.unwrap();
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        flush_pending_records(&mut state, &config, &tx).await;
+
+        let messages = collect_messages(rx).await;
+        assert_eq!(
+            messages,
+            vec![
+                vec!["ERROR boom\n    at frame 1\n    at frame 2"],
+                vec!["INFO done"]
+            ]
+        );
+        assert!(state.flush_deadline().is_none());
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_flush_expired_records() {
+        let config = StreamConfig::new().multiline(
+            MultilineConfig::continuation_whitespace().flush_timeout(Duration::from_millis(10)),
+        );
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+        state
+            .multiline
+            .as_mut()
+            .unwrap()
+            .push_all(vec![Record::new("held")]);
+
+        // Not expired yet
+        flush_expired_records(&mut state, &config, &tx).await;
+        assert!(state.flush_deadline().is_some());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        flush_expired_records(&mut state, &config, &tx).await;
+
+        let messages = collect_messages(rx).await;
+        assert_eq!(messages, vec![vec!["held"]]);
+        assert_eq!(state.stats.lines_emitted, 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_with_parser() {
+        let temp_file = "test_read_with_parser.tmp";
+        fs::write(temp_file, "level=info msg=\"started\"\nplain\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().parser(|line: &str| {
+            line.starts_with("level=")
+                .then(|| LogfmtParser::new().parse(line))
+                .flatten()
+        });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "level=info msg=\"started\"");
+        assert_eq!(records[0].fields.get_str("msg"), Some("started"));
+        assert_eq!(records[1].text, "plain");
+        assert!(records[1].fields.is_empty());
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_filter() {
+        let temp_file = "test_read_with_filter.tmp";
+        fs::write(temp_file, "ERROR one\nINFO two\nERROR GET /health\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let filter = RecordFilter::new()
+            .include(["ERROR"])
+            .unwrap()
+            .exclude(["/health"])
+            .unwrap();
+        let config = StreamConfig::new().filter(filter);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "ERROR one");
+
+        // A read where every line is filtered sends no batch at all
+        fs::write(
+            temp_file,
+            "ERROR one\nINFO two\nERROR GET /health\nINFO three\n",
+        )
+        .await
+        .unwrap();
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(state.stats.records_filtered, 3);
+        assert_eq!(state.stats.batches_emitted, 1);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_redaction() {
+        let temp_file = "test_read_with_redaction.tmp";
+        fs::write(temp_file, "user=a@b.io ip=10.0.0.1\nuser=c@d.io\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .parser(LogfmtParser::new())
+            .redaction(RedactionConfig::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records[0].text, "user=[REDACTED:email] ip=[REDACTED:ipv4]");
+        assert_eq!(records[1].fields.get_str("user"), Some("[REDACTED:email]"));
+        assert_eq!(state.stats.redactions.get("email"), Some(&2));
+        assert_eq!(state.stats.redactions.get("ipv4"), Some(&1));
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_pipeline() {
+        let temp_file = "test_read_with_pipeline.tmp";
+        fs::write(temp_file, "a@b.io,b\nskip\nc\n").await.unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let pipeline = Pipeline::new()
+            .processor(|record: Record| record.text.split(',').map(Record::new).collect())
+            .processor(|record: Record| match record.text.as_str() {
+                "skip" => Vec::new(),
+                _ => vec![record],
+            })
+            .async_processor(|record: Record| async move {
+                vec![Record::new(format!("{} done", record.text))]
+            });
+        let config = StreamConfig::new()
+            .pipeline(pipeline)
+            .redaction(RedactionConfig::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let texts: Vec<String> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        // Processors only see redacted text
+        assert_eq!(texts, vec!["[REDACTED:email] done", "b done", "c done"]);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dedup_summaries_skip_sampling() {
+        let temp_file = "test_dedup_summaries_skip_sampling.tmp";
+        fs::write(temp_file, "a\na\na\nb\nc\n").await.unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .dedup(DedupConfig::new())
+            .sampling(SamplingConfig::new().max_per_second(2).burst(2));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let texts: Vec<String> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        assert_eq!(texts, vec!["a", "last message repeated 2 times", "b"]);
+        assert_eq!(state.stats.records_deduplicated, 2);
+        assert_eq!(state.stats.records_sampled, 1);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_dedup() {
+        let temp_file = "test_read_with_dedup.tmp";
+        fs::write(
+            temp_file,
+            "start\nconn 1 refused\nconn 2 refused\nconn 3 refused\n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().dedup(
+            DedupConfig::new()
+                .normalize(true)
+                .window(Duration::from_millis(20)),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let texts: Vec<String> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        assert_eq!(texts, vec!["start", "conn 1 refused"]);
+        assert!(state.flush_deadline().is_some());
+
+        // The summary is emitted once the window ends without another record
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        flush_expired_records(&mut state, &config, &tx).await;
+        let summary = rx.try_recv().unwrap().unwrap();
+        assert_eq!(summary[0].text, "last message repeated 2 times");
+        assert_eq!(
+            summary[0].fields.get_str("repeated_text"),
+            Some("conn 3 refused")
+        );
+        assert_eq!(state.stats.records_deduplicated, 2);
+        assert!(state.flush_deadline().is_none());
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_sampling() {
+        let temp_file = "test_read_with_sampling.tmp";
+        fs::write(
+            temp_file,
+            "ERROR a\nDEBUG b\nDEBUG c\nINFO d\nINFO e\nINFO f\n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let sampling = SamplingConfig::new()
+            .level_rate(Level::Debug, 0.0)
+            .max_per_second(3);
+        let config = StreamConfig::new()
+            .levels(LevelConfig::new())
+            .sampling(sampling);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let texts: Vec<String> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        assert_eq!(texts, vec!["ERROR a", "INFO d", "INFO e"]);
+        assert_eq!(state.stats.records_sampled, 3);
+
+        // The summary of what was dropped comes out when the stream ends
+        flush_pending_records(&mut state, &config, &tx).await;
+        let summary = rx.try_recv().unwrap().unwrap();
+        assert_eq!(
+            summary[0].text,
+            "dropped 3 records (2 sampled, 1 rate limited)"
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_context_lines_skip_dedup_and_sampling() {
+        let temp_file = "test_context_lines_skip_dedup_and_sampling.tmp";
+        fs::write(temp_file, "ERROR x\nsame\nsame\nskip\nskip\nERROR y\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let filter = RecordFilter::new().include(["ERROR"]).unwrap().after(2);
+        let config = StreamConfig::new()
+            .filter(filter)
+            .dedup(DedupConfig::new())
+            .sampling(SamplingConfig::new().max_per_second(1).burst(1));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let emitted: Vec<(String, Option<MatchContext>)> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.text, record.context))
+            .collect();
+        assert_eq!(
+            emitted,
+            vec![
+                ("ERROR x".to_string(), Some(MatchContext::Match)),
+                ("same".to_string(), Some(MatchContext::After)),
+                ("same".to_string(), Some(MatchContext::After)),
+                ("--".to_string(), Some(MatchContext::Separator)),
+            ]
+        );
+        assert_eq!(state.stats.records_deduplicated, 0);
+        assert_eq!(state.stats.records_sampled, 1);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_context_lines() {
+        let temp_file = "test_read_with_context_lines.tmp";
+        fs::write(temp_file, "a\nb\nERROR one\nc\nd\ne\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let filter = RecordFilter::new().include(["ERROR"]).unwrap().context(1);
+        let config = StreamConfig::new().filter(filter);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let records = rx.try_recv().unwrap().unwrap();
+        let emitted: Vec<(&str, Option<MatchContext>)> = records
+            .iter()
+            .map(|record| (record.text.as_str(), record.context))
+            .collect();
+        assert_eq!(
+            emitted,
+            vec![
+                ("b", Some(MatchContext::Before)),
+                ("ERROR one", Some(MatchContext::Match)),
+                ("c", Some(MatchContext::After)),
+            ]
+        );
+
+        // Context before a match in a later read comes from earlier reads
+        fs::write(temp_file, "a\nb\nERROR one\nc\nd\ne\nERROR two\n")
+            .await
+            .unwrap();
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+        let texts: Vec<String> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.text)
+            .collect();
+        assert_eq!(texts, vec!["--", "e", "ERROR two"]);
+        assert_eq!(state.stats.records_filtered, 2);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_min_level() {
+        let temp_file = "test_read_with_min_level.tmp";
+        fs::write(
+            temp_file,
+            "INFO started\nlevel=error msg=failed\nDEBUG tick\n  continuation\n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new().levels(LevelConfig::new().min_level(Level::Warn));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let records = rx.try_recv().unwrap().unwrap();
+        let levels: Vec<Option<Level>> = records.iter().map(|record| record.level).collect();
+        assert_eq!(levels, vec![Some(Level::Error), None]);
+        assert_eq!(state.stats.records_filtered, 2);
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_with_timestamps() {
+        let temp_file = "test_read_with_timestamps.tmp";
+        fs::write(
+            temp_file,
+            "ts=2026-10-17T12:00:00Z msg=parsed\n2026-10-17 13:00:00 from text\nnone\n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .parser(LogfmtParser::new())
+            .timestamps(TimestampConfig::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let timestamps: Vec<Option<String>> = rx
+            .try_recv()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.timestamp.map(|t| t.to_rfc3339()))
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                Some("2026-10-17T12:00:00+00:00".to_string()),
+                Some("2026-10-17T13:00:00+00:00".to_string()),
+                None,
+            ]
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_records_takes_timestamp_from_parser() {
+        let config = StreamConfig::new().parser(AccessLogParser::common());
+        let records = parse_records(
+            [
+                Record::new(r#"127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.1" 200 5"#),
+                Record::new("plain line"),
+            ],
+            &config,
+        );
+
+        let timestamps: Vec<Option<String>> = records
+            .iter()
+            .map(|record| record.timestamp.map(|t| t.to_rfc3339()))
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![Some("2000-10-10T13:55:36-07:00".to_string()), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_csv_header_from_start_and_after_truncation() {
+        let temp_file = "test_read_csv_header.tmp";
+        fs::write(temp_file, "id,msg\n1,old\n").await.unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .buffer_partial_records(true)
+            .delimited(DelimitedFormat::csv());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        // Start past the existing rows, as with start_at_end
+        state.position = fs::metadata(temp_file).await.unwrap().len();
+        fs::write(temp_file, "id,msg\n1,old\n2,\"multi\nline\"\n")
+            .await
+            .unwrap();
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields.get_str("id"), Some("2"));
+        assert_eq!(records[0].fields.get_str("msg"), Some("multi\nline"));
+
+        // A rotated file brings its own header
+        fs::write(temp_file, "level,text\nINFO,new\n")
+            .await
+            .unwrap();
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields.get_str("level"), Some("INFO"));
+        assert_eq!(records[0].fields.get_str("text"), Some("new"));
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_parser_adds_to_csv_columns() {
+        let temp_file = "test_parser_adds_to_csv_columns.tmp";
+        fs::write(temp_file, "id,msg\n1,login user=bob\n2,logout\n")
+            .await
+            .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .delimited(DelimitedFormat::csv())
+            .parser(RegexParser::new(r"user=(?P<user>\w+)").unwrap());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].fields.to_string(),
+            "{id: 1, msg: login user=bob, user: bob}"
+        );
+        assert_eq!(records[1].fields.to_string(), "{id: 2, msg: logout}");
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_cri_container_log() {
+        let temp_file = "test_read_cri_container_log.tmp";
+        fs::write(
+            temp_file,
+            "2024-01-01T00:00:00Z stdout P level=info \n2024-01-01T00:00:00Z stdout F msg=ok\n2024-01-01T00:00:01Z stderr F \n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .container_format(ContainerFormat::Cri)
+            .parser(LogfmtParser::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        // The partial chunk is joined and the empty stderr line dropped
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text, "level=info msg=ok");
+        assert_eq!(records[0].fields.get_str("msg"), Some("ok"));
+        assert_eq!(records[0].metadata.get_str("stream"), Some("stdout"));
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_audit_events() {
+        let temp_file = "test_read_audit_events.tmp";
+        fs::write(
+            temp_file,
+            "type=SYSCALL msg=audit(1700000000.100:7): syscall=59 comm=\"ls\"\n\
+             type=PROCTITLE msg=audit(1700000000.100:7): proctitle=6C73002D6C\n\
+             type=EOE msg=audit(1700000000.100:7): \n\
+             type=DAEMON_START msg=audit(1700000001.000:8): op=start res=success\n",
+        )
+        .await
+        .unwrap();
+
+        let file_path = PathBuf::from(temp_file);
+        let config = StreamConfig::new()
+            .audit_events(AuditConfig::new().flush_timeout(Duration::from_millis(10)))
+            .parser(RegexParser::new(r#"comm="(?P<command>\w+)""#).unwrap());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState::new(&config);
+
+        read_file_content(&file_path, &mut state, &config, &tx)
+            .await
+            .unwrap();
+
+        // The event ended by EOE is emitted right away
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].text.lines().count(), 2);
+        assert_eq!(
+            records[0].fields.get("serial").and_then(|v| v.as_i64()),
+            Some(7)
+        );
+        // The parsed fields are added to the assembled ones
+        assert_eq!(records[0].fields.get_str("command"), Some("ls"));
+
+        // The single record event has no EOE and waits for the timeout
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        flush_expired_records(&mut state, &config, &tx).await;
+        let records = rx.try_recv().unwrap().unwrap();
+        assert_eq!(
+            records[0].fields.get("serial").and_then(|v| v.as_i64()),
+            Some(8)
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).await.unwrap();
+    }
+
     #[test]
     fn test_detect_file_truncation() {
         assert!(detect_file_truncation(100, 200)); // File was truncated
@@ -186,9 +1522,9 @@ mod tests {
     async fn test_read_simple_file_with_newline_separator() {
         let file_path = PathBuf::from("fixtures/simple_append.log");
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read file successfully");
 
@@ -217,16 +1553,16 @@ mod tests {
 
         // Position should be at the end of file
         let metadata = fs::metadata(&file_path).await.unwrap();
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
     }
 
     #[tokio::test]
     async fn test_read_file_with_different_separator() {
         let file_path = PathBuf::from("fixtures/different_separators.log");
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "|", &tx)
+        read_file_content(&file_path, &mut state, &with_separator("|"), &tx)
             .await
             .expect("Should read file successfully");
 
@@ -243,7 +1579,7 @@ mod tests {
         assert!(lines[1].contains("Loading configuration"));
 
         let metadata = fs::metadata(&file_path).await.unwrap();
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
     }
 
     #[tokio::test]
@@ -259,10 +1595,13 @@ mod tests {
             .read_to_string(&mut content)
             .await
             .unwrap();
-        let mut position = first_chunk_size;
+        let mut state = ReaderState {
+            position: first_chunk_size,
+            ..Default::default()
+        };
 
         // Now read from position 50 to end
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read remaining content");
 
@@ -290,49 +1629,74 @@ mod tests {
 
         // Position should be at end of file
         let metadata = fs::metadata(&file_path).await.unwrap();
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
     }
 
     #[tokio::test]
     async fn test_file_truncation_handling() {
         let file_path = PathBuf::from("fixtures/simple_append.log");
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 1000u64; // Set position beyond file size
+        let mut state = ReaderState {
+            position: 1000, // Set position beyond file size
+            ..Default::default()
+        };
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should handle truncation");
 
         let messages = collect_messages(rx).await;
 
         // Should read all content from beginning due to truncation detection
-        assert!(messages.len() > 0);
+        assert!(!messages.is_empty());
 
         // Position should be reset and then set to end of file
         let metadata = fs::metadata(&file_path).await.unwrap();
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
+    }
+
+    #[tokio::test]
+    async fn test_read_updates_stats() {
+        let file_path = PathBuf::from("fixtures/simple_append.log");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut state = ReaderState {
+            position: 1000, // Force truncation detection
+            ..Default::default()
+        };
+
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
+            .await
+            .expect("Should read file successfully");
+
+        let metadata = fs::metadata(&file_path).await.unwrap();
+        let stats = state.into_stats();
+        assert_eq!(stats.bytes_read, metadata.len());
+        assert_eq!(stats.lines_emitted, 10);
+        assert_eq!(stats.batches_emitted, 1);
+        assert_eq!(stats.truncations, 1);
+        assert_eq!(stats.position, metadata.len());
     }
 
     #[tokio::test]
     async fn test_nonexistent_file() {
         let file_path = PathBuf::from("fixtures/nonexistent.log");
         let (tx, _rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        let result = read_file_content(&file_path, &mut position, "\n", &tx).await;
+        let result = read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx).await;
 
         // Should not error for non-existent file
         assert!(result.is_ok());
-        assert_eq!(position, 0);
+        assert_eq!(state.position, 0);
     }
 
     #[tokio::test]
     async fn test_empty_file() {
         let file_path = PathBuf::from("fixtures/empty.log");
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should handle empty file");
 
@@ -343,7 +1707,7 @@ mod tests {
 
         // Position should match file size (which is minimal for empty file)
         let metadata = fs::metadata(&file_path).await.unwrap();
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
     }
 
     #[tokio::test]
@@ -353,9 +1717,12 @@ mod tests {
 
         // Set position to file size (at end)
         let metadata = fs::metadata(&file_path).await.unwrap();
-        let mut position = metadata.len();
+        let mut state = ReaderState {
+            position: metadata.len(),
+            ..Default::default()
+        };
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should handle no new content");
 
@@ -363,20 +1730,20 @@ mod tests {
 
         // Should produce no messages when already at end
         assert_eq!(messages.len(), 0);
-        assert_eq!(position, metadata.len());
+        assert_eq!(state.position, metadata.len());
     }
 
     #[tokio::test]
     async fn test_receiver_dropped() {
         let file_path = PathBuf::from("fixtures/simple_append.log");
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
         // Drop the receiver to simulate channel closure
         drop(rx);
 
         // Should not panic and should complete successfully
-        let result = read_file_content(&file_path, &mut position, "\n", &tx).await;
+        let result = read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx).await;
         assert!(result.is_ok());
     }
 
@@ -390,9 +1757,9 @@ mod tests {
 
         let file_path = PathBuf::from(temp_file);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read file successfully");
 
@@ -418,9 +1785,9 @@ mod tests {
 
         let file_path = PathBuf::from(temp_file);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read UTF-8 content successfully");
 
@@ -450,9 +1817,9 @@ mod tests {
 
         let file_path = PathBuf::from(temp_file);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read large file successfully");
 
@@ -480,9 +1847,9 @@ mod tests {
 
         let file_path = PathBuf::from(temp_file);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read file with long lines successfully");
 
@@ -509,9 +1876,9 @@ mod tests {
 
         let file_path = PathBuf::from(temp_file);
         let (tx, rx) = mpsc::unbounded_channel();
-        let mut position = 0u64;
+        let mut state = ReaderState::default();
 
-        read_file_content(&file_path, &mut position, "\n", &tx)
+        read_file_content(&file_path, &mut state, &StreamConfig::default(), &tx)
             .await
             .expect("Should read binary-like content successfully");
 
@@ -539,9 +1906,9 @@ mod tests {
             let path = file_path.clone();
             let handle = tokio::spawn(async move {
                 let (tx, rx) = mpsc::unbounded_channel();
-                let mut position = 0u64;
+                let mut state = ReaderState::default();
 
-                read_file_content(&path, &mut position, "\n", &tx)
+                read_file_content(&path, &mut state, &StreamConfig::default(), &tx)
                     .await
                     .expect("Should read file successfully");
 
@@ -569,22 +1936,22 @@ mod tests {
     fn test_split_and_filter_content_edge_cases() {
         // Test with separator at the beginning
         let content = "\nline1\nline2";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2"]);
 
         // Test with separator at the end
         let content = "line1\nline2\n";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2"]);
 
         // Test with repeated separators
         let content = "line1\n\n\n\nline2";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2"]);
 
         // Test with whitespace-only content between separators
         let content = "line1\n   \n\t\n  \nline2";
-        let result = split_and_filter_content(content, "\n");
+        let result = split_and_filter_content(content, &with_separator("\n"));
         assert_eq!(result, vec!["line1", "line2"]);
     }
 
@@ -0,0 +1,338 @@
+//! Structured records produced by parsers.
+
+use crate::filter::MatchContext;
+use crate::level::Level;
+use chrono::{DateTime, FixedOffset};
+use std::fmt;
+
+/// A single value stored in a record field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// An explicit null or missing value.
+    Null,
+    /// A boolean value.
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    String(String),
+    /// A point in time, keeping the offset it was logged with.
+    Timestamp(DateTime<FixedOffset>),
+    /// An ordered list of values.
+    List(Vec<FieldValue>),
+    /// Nested fields.
+    Map(Fields),
+}
+
+impl FieldValue {
+    /// Returns the value as a string slice if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer, parsing strings if needed.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FieldValue::Int(value) => Some(*value),
+            FieldValue::String(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, converting integers and parsing strings if needed.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Int(value) => Some(*value as f64),
+            FieldValue::Float(value) => Some(*value),
+            FieldValue::String(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a timestamp if it is one.
+    pub fn as_timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            FieldValue::Timestamp(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a boolean, parsing `true`/`false` strings if needed.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FieldValue::Bool(value) => Some(*value),
+            FieldValue::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Null => write!(f, "null"),
+            FieldValue::Bool(value) => write!(f, "{}", value),
+            FieldValue::Int(value) => write!(f, "{}", value),
+            FieldValue::Float(value) => write!(f, "{}", value),
+            FieldValue::String(value) => write!(f, "{}", value),
+            FieldValue::Timestamp(value) => write!(f, "{}", value.to_rfc3339()),
+            FieldValue::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            FieldValue::Map(fields) => write!(f, "{}", fields),
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::String(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::String(value)
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self {
+        FieldValue::Int(value)
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::Float(value)
+    }
+}
+
+impl From<DateTime<FixedOffset>> for FieldValue {
+    fn from(value: DateTime<FixedOffset>) -> Self {
+        FieldValue::Timestamp(value)
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+/// An ordered map of field names to values.
+///
+/// Fields keep the order in which they were first inserted, matching the order
+/// they appeared in the original line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fields(Vec<(String, FieldValue)>);
+
+impl Fields {
+    /// Creates an empty field map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a field, replacing any existing value while keeping its position.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<FieldValue>) {
+        let key = key.into();
+        let value = value.into();
+        match self.0.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    /// Returns the value of a field.
+    pub fn get(&self, key: &str) -> Option<&FieldValue> {
+        self.0
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of a field if it is a string.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(FieldValue::as_str)
+    }
+
+    /// Removes a field and returns its value.
+    pub fn remove(&mut self, key: &str) -> Option<FieldValue> {
+        let index = self.0.iter().position(|(existing, _)| existing == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    /// Returns true if the field is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of fields.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over fields in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FieldValue)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Iterates over fields in order, allowing the values to be changed.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut FieldValue)> {
+        self.0.iter_mut().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Adds all fields from another map, replacing existing values.
+    pub fn extend(&mut self, other: Fields) {
+        for (key, value) in other {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl fmt::Display for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", key, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl IntoIterator for Fields {
+    type Item = (String, FieldValue);
+    type IntoIter = std::vec::IntoIter<(String, FieldValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<K: Into<String>, V: Into<FieldValue>> FromIterator<(K, V)> for Fields {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut fields = Fields::new();
+        for (key, value) in iter {
+            fields.insert(key, value);
+        }
+        fields
+    }
+}
+
+/// A record read from the log, along with any fields extracted by a parser.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    /// The record text as read from the file.
+    pub text: String,
+    /// Fields extracted by the configured parser, empty if there is none or it did not match.
+    pub fields: Fields,
+    /// Where the record came from, such as the container `stream` and runtime `time`.
+    pub metadata: Fields,
+    /// When the event happened, if timestamp extraction is configured and found one.
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    /// The severity of the record, if level detection is configured and found one.
+    pub level: Option<Level>,
+    /// Whether the record matched the filter or surrounds a match, if context lines are configured.
+    pub context: Option<MatchContext>,
+}
+
+impl Record {
+    /// Creates a record without any fields.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            fields: Fields::new(),
+            metadata: Fields::new(),
+            timestamp: None,
+            level: None,
+            context: None,
+        }
+    }
+}
+
+impl From<Record> for String {
+    fn from(record: Record) -> Self {
+        record.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_preserve_insertion_order() {
+        let mut fields = Fields::new();
+        fields.insert("level", "info");
+        fields.insert("msg", "started");
+        fields.insert("port", 8080);
+
+        let keys: Vec<&str> = fields.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["level", "msg", "port"]);
+    }
+
+    #[test]
+    fn test_fields_insert_replaces_in_place() {
+        let mut fields: Fields = [("a", 1), ("b", 2)].into_iter().collect();
+        fields.insert("a", 3);
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields.get("a"), Some(&FieldValue::Int(3)));
+        assert_eq!(fields.iter().next().unwrap().0, "a");
+    }
+
+    #[test]
+    fn test_fields_remove() {
+        let mut fields: Fields = [("a", "x"), ("b", "y")].into_iter().collect();
+        assert_eq!(fields.remove("a"), Some(FieldValue::from("x")));
+        assert_eq!(fields.remove("missing"), None);
+        assert!(!fields.contains_key("a"));
+        assert_eq!(fields.get_str("b"), Some("y"));
+    }
+
+    #[test]
+    fn test_field_value_conversions() {
+        assert_eq!(FieldValue::from("42").as_i64(), Some(42));
+        assert_eq!(FieldValue::Int(2).as_f64(), Some(2.0));
+        assert_eq!(FieldValue::from("1.5").as_f64(), Some(1.5));
+        assert_eq!(FieldValue::from("true").as_bool(), Some(true));
+        assert_eq!(FieldValue::from("abc").as_i64(), None);
+        assert_eq!(FieldValue::Null.as_str(), None);
+    }
+
+    #[test]
+    fn test_field_value_display() {
+        let mut nested = Fields::new();
+        nested.insert("k", "v");
+        let value = FieldValue::List(vec![FieldValue::Int(1), FieldValue::Map(nested)]);
+        assert_eq!(value.to_string(), "[1, {k: v}]");
+        assert_eq!(FieldValue::Null.to_string(), "null");
+    }
+
+    #[test]
+    fn test_record_into_string() {
+        let record = Record::new("hello");
+        assert!(record.fields.is_empty());
+        assert_eq!(String::from(record), "hello");
+    }
+}
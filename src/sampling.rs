@@ -0,0 +1,454 @@
+//! Sampling and rate limiting of emitted records.
+
+use crate::level::Level;
+use crate::record::{FieldValue, Fields, Record};
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How records are picked when only a fraction of them is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SampleMode {
+    /// Keep each record with the configured probability.
+    #[default]
+    Random,
+    /// Keep records whose hash falls under the rate, so the same text (or
+    /// [`hash_field`](SamplingConfig::hash_field) value) is always kept or
+    /// always dropped, across streams and restarts.
+    Hash,
+}
+
+/// Configuration for sampling records and capping how many are emitted.
+///
+/// Records are first sampled at the rate for their [`Level`] (the default
+/// rate for records without a level), then the survivors pass through a
+/// token bucket refilled at [`max_per_second`](Self::max_per_second). The
+/// rate limit applies to every level. Whenever records are dropped, a summary
+/// record is emitted after [`summary_interval`](Self::summary_interval) with
+/// `dropped`, `rate_limited` and `sampled` (counts by level) fields.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{Level, LevelConfig, SampleMode, SamplingConfig, StreamConfig};
+///
+/// // Keep every warning and error, 1% of debug and trace and 10% of the rest
+/// let sampling = SamplingConfig::new()
+///     .rate(0.1)
+///     .level_rate(Level::Warn, 1.0)
+///     .level_rate(Level::Error, 1.0)
+///     .level_rate(Level::Fatal, 1.0)
+///     .level_rate(Level::Debug, 0.01)
+///     .level_rate(Level::Trace, 0.01)
+///     .mode(SampleMode::Hash)
+///     .max_per_second(1_000);
+/// let config = StreamConfig::new()
+///     .levels(LevelConfig::new())
+///     .sampling(sampling);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingConfig {
+    pub(crate) rate: f64,
+    pub(crate) level_rates: Vec<(Level, f64)>,
+    pub(crate) mode: SampleMode,
+    pub(crate) hash_field: Option<String>,
+    pub(crate) max_per_second: Option<u32>,
+    pub(crate) burst: Option<u32>,
+    pub(crate) summary_interval: Duration,
+}
+
+impl SamplingConfig {
+    /// Default time dropped records are counted before a summary is emitted.
+    pub const DEFAULT_SUMMARY_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// Creates a configuration that keeps every record.
+    pub fn new() -> Self {
+        Self {
+            rate: 1.0,
+            level_rates: Vec::new(),
+            mode: SampleMode::Random,
+            hash_field: None,
+            max_per_second: None,
+            burst: None,
+            summary_interval: Self::DEFAULT_SUMMARY_INTERVAL,
+        }
+    }
+
+    /// Sets the fraction of records kept, from 0.0 to 1.0, for levels without their own rate.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the fraction of records kept at `level`, from 0.0 to 1.0.
+    pub fn level_rate(mut self, level: Level, rate: f64) -> Self {
+        self.level_rates.retain(|(existing, _)| *existing != level);
+        self.level_rates.push((level, rate.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Sets how sampled records are picked.
+    pub fn mode(mut self, mode: SampleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Hashes this field instead of the record text in [`SampleMode::Hash`],
+    /// so for example every record of a sampled trace is kept.
+    pub fn hash_field(mut self, field: impl Into<String>) -> Self {
+        self.hash_field = Some(field.into());
+        self
+    }
+
+    /// Caps the records emitted to an average of `count` per second. A count
+    /// of 0 drops every record, whatever the burst.
+    pub fn max_per_second(mut self, count: u32) -> Self {
+        self.max_per_second = Some(count);
+        self
+    }
+
+    /// Sets how many records can be emitted at once before the rate limit
+    /// applies (defaults to one second's worth).
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// Sets how long dropped records are counted before a summary is emitted.
+    pub fn summary_interval(mut self, interval: Duration) -> Self {
+        self.summary_interval = interval;
+        self
+    }
+
+    fn rate_for(&self, level: Option<Level>) -> f64 {
+        level
+            .and_then(|level| {
+                self.level_rates
+                    .iter()
+                    .find(|(existing, _)| *existing == level)
+            })
+            .map_or(self.rate, |(_, rate)| *rate)
+    }
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A token bucket allowing `rate` records per second with bursts of up to `capacity`.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, burst: Option<u32>, now: Instant) -> Self {
+        let capacity = match rate {
+            0 => 0.0,
+            _ => f64::from(burst.unwrap_or(rate).max(1)),
+        };
+        Self {
+            rate: f64::from(rate),
+            capacity,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    fn take(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Samples and rate limits records according to a [`SamplingConfig`].
+#[derive(Debug)]
+pub(crate) struct Sampler {
+    config: SamplingConfig,
+    bucket: Option<TokenBucket>,
+    random: u64,
+    sampled: BTreeMap<Option<Level>, u64>,
+    rate_limited: u64,
+    dropped_since: Option<Instant>,
+    dropped: u64,
+}
+
+impl Sampler {
+    pub(crate) fn new(config: SamplingConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            bucket: config
+                .max_per_second
+                .map(|rate| TokenBucket::new(rate, config.burst, now)),
+            config,
+            random: RandomState::new().hash_one(now),
+            sampled: BTreeMap::new(),
+            rate_limited: 0,
+            dropped_since: None,
+            dropped: 0,
+        }
+    }
+
+    /// Returns the records that are kept, starting with a summary of earlier
+    /// drops if the summary interval has passed.
+    pub(crate) fn push_all(&mut self, records: Vec<Record>, now: Instant) -> Vec<Record> {
+        let mut output: Vec<Record> = self.flush_expired(now).into_iter().collect();
+
+        for record in records {
+            if !self.sample(&record) {
+                *self.sampled.entry(record.level).or_default() += 1;
+            } else if self.bucket.as_mut().is_some_and(|bucket| !bucket.take(now)) {
+                self.rate_limited += 1;
+            } else {
+                output.push(record);
+                continue;
+            }
+            self.dropped += 1;
+            self.dropped_since.get_or_insert(now);
+        }
+        output
+    }
+
+    /// Emits a summary of the records dropped since the last one, if any.
+    pub(crate) fn flush(&mut self) -> Option<Record> {
+        self.dropped_since.take()?;
+
+        let sampled = std::mem::take(&mut self.sampled);
+        let sampled_total: u64 = sampled.values().sum();
+        let rate_limited = std::mem::take(&mut self.rate_limited);
+        let dropped = sampled_total + rate_limited;
+        let by_level: Fields = sampled
+            .into_iter()
+            .map(|(level, count)| {
+                let name = level.map_or("UNKNOWN", |level| level.as_str());
+                (name, count as i64)
+            })
+            .collect();
+
+        let mut record = Record::new(format!(
+            "dropped {} records ({} sampled, {} rate limited)",
+            dropped, sampled_total, rate_limited
+        ));
+        record.fields.insert("dropped", dropped as i64);
+        record.fields.insert("rate_limited", rate_limited as i64);
+        record.fields.insert("sampled", FieldValue::Map(by_level));
+        Some(record)
+    }
+
+    /// Returns when the next summary is due.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        self.dropped_since
+            .map(|since| since + self.config.summary_interval)
+    }
+
+    /// Returns how many records have been dropped so far.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn flush_expired(&mut self, now: Instant) -> Option<Record> {
+        if self.flush_deadline()? <= now {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Decide whether a record survives sampling
+    fn sample(&mut self, record: &Record) -> bool {
+        let rate = self.config.rate_for(record.level);
+        if rate >= 1.0 {
+            return true;
+        }
+        if rate <= 0.0 {
+            return false;
+        }
+
+        let value = match self.config.mode {
+            SampleMode::Random => self.next_random(),
+            SampleMode::Hash => {
+                let field = self
+                    .config
+                    .hash_field
+                    .as_deref()
+                    .and_then(|name| record.fields.get(name));
+                match field {
+                    Some(value) => fnv1a(value.to_string().as_bytes()),
+                    None => fnv1a(record.text.as_bytes()),
+                }
+            }
+        };
+        (value as f64 / u64::MAX as f64) < rate
+    }
+
+    /// SplitMix64, plenty for picking which records to keep
+    fn next_random(&mut self) -> u64 {
+        self.random = self.random.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.random;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// FNV-1a, a hash that stays the same across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(count: usize, level: Option<Level>) -> Vec<Record> {
+        (0..count)
+            .map(|i| Record {
+                level,
+                ..Record::new(format!("line {}", i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_level_rates() {
+        let config = SamplingConfig::new()
+            .rate(0.0)
+            .level_rate(Level::Error, 1.0)
+            .level_rate(Level::Debug, 0.1);
+        let mut sampler = Sampler::new(config);
+        let now = Instant::now();
+
+        let mut input = records(10, Some(Level::Error));
+        input.extend(records(2000, Some(Level::Debug)));
+        input.extend(records(5, None));
+        let kept = sampler.push_all(input, now);
+
+        let errors = kept
+            .iter()
+            .filter(|r| r.level == Some(Level::Error))
+            .count();
+        let debug = kept
+            .iter()
+            .filter(|r| r.level == Some(Level::Debug))
+            .count();
+        assert_eq!(errors, 10);
+        assert!((100..300).contains(&debug), "kept {} debug records", debug);
+        assert!(kept.iter().all(|r| r.level.is_some()));
+        assert_eq!(sampler.dropped(), (2005 - debug) as u64);
+    }
+
+    #[test]
+    fn test_hash_mode_is_deterministic() {
+        let config = SamplingConfig::new().rate(0.5).mode(SampleMode::Hash);
+        let now = Instant::now();
+        let first = Sampler::new(config.clone()).push_all(records(200, None), now);
+        let second = Sampler::new(config).push_all(records(200, None), now);
+        assert_eq!(first, second);
+        assert!((50..150).contains(&first.len()));
+
+        // Records sharing the hashed field are kept or dropped together
+        let config = SamplingConfig::new()
+            .rate(0.5)
+            .mode(SampleMode::Hash)
+            .hash_field("trace");
+        let mut sampler = Sampler::new(config);
+        let traced: Vec<Record> = (0..20)
+            .map(|i| {
+                let mut record = Record::new(format!("span {}", i));
+                record.fields.insert("trace", format!("t{}", i % 2));
+                record
+            })
+            .collect();
+        let kept = sampler.push_all(traced, now);
+        for trace in ["t0", "t1"] {
+            let count = kept
+                .iter()
+                .filter(|r| r.fields.get_str("trace") == Some(trace))
+                .count();
+            assert!(count == 0 || count == 10, "kept {} of {}", count, trace);
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let config = SamplingConfig::new().max_per_second(10).burst(5);
+        let mut sampler = Sampler::new(config);
+        let start = Instant::now();
+
+        assert_eq!(sampler.push_all(records(8, None), start).len(), 5);
+        // Half a second refills five tokens
+        let later = start + Duration::from_millis(500);
+        assert_eq!(sampler.push_all(records(8, None), later).len(), 5);
+        assert_eq!(sampler.dropped(), 6);
+    }
+
+    #[test]
+    fn test_rate_limit_of_zero_drops_everything() {
+        let start = Instant::now();
+        for config in [
+            SamplingConfig::new().max_per_second(0),
+            SamplingConfig::new().max_per_second(0).burst(5),
+        ] {
+            let mut sampler = Sampler::new(config);
+            assert!(sampler.push_all(records(3, None), start).is_empty());
+            let later = start + Duration::from_millis(500);
+            assert!(sampler.push_all(records(3, None), later).is_empty());
+            assert_eq!(sampler.dropped(), 6);
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let interval = Duration::from_secs(5);
+        let config = SamplingConfig::new()
+            .rate(0.0)
+            .level_rate(Level::Info, 1.0)
+            .max_per_second(1)
+            .summary_interval(interval);
+        let mut sampler = Sampler::new(config);
+        let start = Instant::now();
+
+        assert!(sampler.flush_deadline().is_none());
+        let mut input = records(3, Some(Level::Info));
+        input.extend(records(4, Some(Level::Debug)));
+        assert_eq!(sampler.push_all(input, start).len(), 1);
+        assert_eq!(sampler.flush_deadline(), Some(start + interval));
+
+        let output = sampler.push_all(Vec::new(), start + interval);
+        let summary = &output[0];
+        assert_eq!(
+            summary.text,
+            "dropped 6 records (4 sampled, 2 rate limited)"
+        );
+        assert_eq!(summary.fields.get("dropped").unwrap().as_i64(), Some(6));
+        assert_eq!(
+            summary.fields.get("rate_limited").unwrap().as_i64(),
+            Some(2)
+        );
+        assert_eq!(
+            summary.fields.get("sampled").unwrap().to_string(),
+            "{DEBUG: 4}"
+        );
+        assert!(sampler.flush_deadline().is_none());
+        assert!(sampler.flush().is_none());
+    }
+}
@@ -0,0 +1,477 @@
+//! Record processing with user scripts written in Rhai.
+
+use crate::error::{Error, Result};
+use crate::processor::LineProcessor;
+use crate::record::{FieldValue, Fields, Record};
+use crate::watcher::{FileWatcher, is_event_relevant_to_file};
+use notify::EventKind;
+use rhai::{AST, Array, CallFnOptions, Dynamic, Engine, Map};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use tokio::task::JoinHandle;
+
+/// Operations a script may run per record before it is stopped, so a
+/// runaway loop cannot stall the reader task.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A compiled script and the source it came from.
+struct Compiled {
+    source: String,
+    ast: Arc<AST>,
+    /// Whether `process` takes the record map as a second argument.
+    with_record: bool,
+}
+
+/// State shared between the processor and its reload task.
+struct Script {
+    engine: Engine,
+    compiled: RwLock<Compiled>,
+    errors: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl Script {
+    fn compile(engine: &Engine, source: String) -> Result<Compiled> {
+        let ast = engine.compile(&source).map_err(|e| Error::Script {
+            message: e.to_string(),
+        })?;
+        let params = ast
+            .iter_functions()
+            .filter(|function| function.name == "process")
+            .map(|function| function.params.len())
+            .max()
+            .ok_or_else(|| Error::Script {
+                message: "script does not define fn process(line)".to_string(),
+            })?;
+
+        Ok(Compiled {
+            source,
+            ast: Arc::new(ast),
+            with_record: params >= 2,
+        })
+    }
+
+    /// Recompile from the file, keeping the current script if it fails
+    fn reload(&self, path: &Path) {
+        let Ok(source) = std::fs::read_to_string(path) else {
+            return;
+        };
+        if self.compiled.read().unwrap().source == source {
+            return;
+        }
+        match Self::compile(&self.engine, source) {
+            Ok(compiled) => *self.compiled.write().unwrap() = compiled,
+            Err(e) => self.set_error(e.to_string()),
+        }
+    }
+
+    fn set_error(&self, message: String) {
+        *self.last_error.lock().unwrap() = Some(message);
+    }
+}
+
+/// Aborts the reload task once the last processor sharing it is dropped.
+struct ReloadTask(JoinHandle<()>);
+
+impl Drop for ReloadTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A [`LineProcessor`] that runs a [Rhai](https://rhai.rs) script on each record.
+///
+/// Available with the `scripting` feature. The script defines
+/// `fn process(line)`, called with the record text, or
+/// `fn process(line, record)`, which also gets a map with the record's
+/// `text`, `fields`, `metadata`, `level` and `timestamp`. What it returns
+/// decides what happens to the record:
+///
+/// * `true` keeps the record unchanged, while `false` or `()` drops it.
+/// * A string replaces the record text.
+/// * A map sets fields on the record, with a `text` entry replacing the text.
+/// * An array emits one record for each element, handled as above.
+///
+/// If the script fails on a record, the record is passed on unchanged and the
+/// failure is counted in [`errors`](Self::errors).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use log_reader::{Pipeline, ScriptProcessor, StreamConfig};
+///
+/// # #[tokio::main]
+/// # async fn main() -> log_reader::Result<()> {
+/// // fn process(line, record) {
+/// //     if line.contains("healthcheck") { return false; }
+/// //     #{ text: line.to_upper(), service: record.fields.app ?? "unknown" }
+/// // }
+/// let script = ScriptProcessor::from_file("transform.rhai")?;
+/// let config = StreamConfig::new().pipeline(Pipeline::new().processor(script));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ScriptProcessor {
+    script: Arc<Script>,
+    reload: Option<Arc<ReloadTask>>,
+}
+
+impl ScriptProcessor {
+    /// Compiles a script from source.
+    pub fn from_source(source: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            script: Arc::new(Self::load(source.into())?),
+            reload: None,
+        })
+    }
+
+    /// Loads a script from a file and reloads it whenever the file changes.
+    ///
+    /// Must be called from within a Tokio runtime. A change that does not
+    /// compile leaves the previous script running and is reported by
+    /// [`last_error`](Self::last_error).
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let handle = tokio::runtime::Handle::try_current().map_err(|e| Error::Script {
+            message: e.to_string(),
+        })?;
+        let script = Arc::new(Self::load(std::fs::read_to_string(&path)?)?);
+
+        let mut watcher = FileWatcher::new(&path)?;
+        watcher.start_watching()?;
+        let task = handle.spawn(reload_on_change(watcher, path, Arc::downgrade(&script)));
+
+        Ok(Self {
+            script,
+            reload: Some(Arc::new(ReloadTask(task))),
+        })
+    }
+
+    fn load(source: String) -> Result<Script> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let compiled = Script::compile(&engine, source)?;
+
+        Ok(Script {
+            engine,
+            compiled: RwLock::new(compiled),
+            errors: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        })
+    }
+
+    /// Returns how many records the script failed on.
+    pub fn errors(&self) -> u64 {
+        self.script.errors.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most recent error from running or reloading the script.
+    pub fn last_error(&self) -> Option<String> {
+        self.script.last_error.lock().unwrap().clone()
+    }
+
+    fn call(&self, record: &Record) -> std::result::Result<Dynamic, String> {
+        let (ast, with_record) = {
+            let compiled = self.script.compiled.read().unwrap();
+            (Arc::clone(&compiled.ast), compiled.with_record)
+        };
+        let options = CallFnOptions::new().eval_ast(false);
+        let mut scope = rhai::Scope::new();
+        let line = record.text.clone();
+
+        let result = if with_record {
+            self.script.engine.call_fn_with_options(
+                options,
+                &mut scope,
+                &ast,
+                "process",
+                (line, record_map(record)),
+            )
+        } else {
+            self.script
+                .engine
+                .call_fn_with_options(options, &mut scope, &ast, "process", (line,))
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+impl LineProcessor for ScriptProcessor {
+    fn process(&self, record: Record) -> Vec<Record> {
+        match self.call(&record) {
+            Ok(result) => apply_result(result, record),
+            Err(message) => {
+                self.script.errors.fetch_add(1, Ordering::Relaxed);
+                self.script.set_error(message);
+                vec![record]
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ScriptProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptProcessor")
+            .field("reloading", &self.reload.is_some())
+            .field("errors", &self.errors())
+            .finish()
+    }
+}
+
+/// Reload the script on each change to its file until the processor is dropped
+async fn reload_on_change(mut watcher: FileWatcher, path: PathBuf, script: Weak<Script>) {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    while let Some(event) = watcher.next_event().await {
+        let Ok(event) = event else {
+            continue;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            || !is_event_relevant_to_file(&event, &file_name)
+        {
+            continue;
+        }
+        match script.upgrade() {
+            Some(script) => script.reload(&path),
+            None => break,
+        }
+    }
+}
+
+/// Turn the value returned by the script into the records to emit
+fn apply_result(result: Dynamic, mut record: Record) -> Vec<Record> {
+    if result.is_unit() {
+        return Vec::new();
+    }
+    if let Ok(keep) = result.as_bool() {
+        return if keep { vec![record] } else { Vec::new() };
+    }
+    if result.is_array() {
+        let array: Array = result.cast();
+        return array
+            .into_iter()
+            .flat_map(|item| apply_result(item, record.clone()))
+            .collect();
+    }
+    if result.is_map() {
+        let map: Map = result.cast();
+        for (key, value) in map {
+            if key == "text" {
+                record.text = value.to_string();
+            } else {
+                record.fields.insert(key.as_str(), to_field(value));
+            }
+        }
+        return vec![record];
+    }
+
+    record.text = result.to_string();
+    vec![record]
+}
+
+/// The record as a map for `fn process(line, record)`
+fn record_map(record: &Record) -> Map {
+    let mut map = Map::new();
+    map.insert("text".into(), record.text.clone().into());
+    map.insert("fields".into(), fields_map(&record.fields).into());
+    map.insert("metadata".into(), fields_map(&record.metadata).into());
+    map.insert(
+        "level".into(),
+        record
+            .level
+            .map_or(Dynamic::UNIT, |level| level.as_str().into()),
+    );
+    map.insert(
+        "timestamp".into(),
+        record
+            .timestamp
+            .map_or(Dynamic::UNIT, |timestamp| timestamp.to_rfc3339().into()),
+    );
+    map
+}
+
+fn fields_map(fields: &Fields) -> Map {
+    fields
+        .iter()
+        .map(|(key, value)| (key.into(), to_dynamic(value)))
+        .collect()
+}
+
+fn to_dynamic(value: &FieldValue) -> Dynamic {
+    match value {
+        FieldValue::Null => Dynamic::UNIT,
+        FieldValue::Bool(value) => (*value).into(),
+        FieldValue::Int(value) => (*value).into(),
+        FieldValue::Float(value) => (*value).into(),
+        FieldValue::String(value) => value.clone().into(),
+        FieldValue::Timestamp(value) => value.to_rfc3339().into(),
+        FieldValue::List(values) => values.iter().map(to_dynamic).collect::<Array>().into(),
+        FieldValue::Map(fields) => fields_map(fields).into(),
+    }
+}
+
+fn to_field(value: Dynamic) -> FieldValue {
+    if value.is_unit() {
+        FieldValue::Null
+    } else if let Ok(value) = value.as_bool() {
+        FieldValue::Bool(value)
+    } else if let Ok(value) = value.as_int() {
+        FieldValue::Int(value)
+    } else if let Ok(value) = value.as_float() {
+        FieldValue::Float(value)
+    } else if value.is_array() {
+        FieldValue::List(value.cast::<Array>().into_iter().map(to_field).collect())
+    } else if value.is_map() {
+        FieldValue::Map(
+            value
+                .cast::<Map>()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), to_field(value)))
+                .collect(),
+        )
+    } else {
+        FieldValue::String(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use std::time::Duration;
+
+    fn texts(records: &[Record]) -> Vec<&str> {
+        records.iter().map(|record| record.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter_and_rewrite() {
+        let script = ScriptProcessor::from_source(
+            r#"
+            fn process(line) {
+                if line.contains("debug") { return false; }
+                if line.starts_with("keep") { return true; }
+                if line == "split" { return ["a", "b"]; }
+                line.to_upper()
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(script.process(Record::new("a debug line")).is_empty());
+        assert_eq!(
+            texts(&script.process(Record::new("keep me"))),
+            vec!["keep me"]
+        );
+        assert_eq!(texts(&script.process(Record::new("split"))), vec!["a", "b"]);
+        assert_eq!(texts(&script.process(Record::new("hello"))), vec!["HELLO"]);
+    }
+
+    #[test]
+    fn test_structured_maps() {
+        let script = ScriptProcessor::from_source(
+            r#"
+            fn process(line, record) {
+                let parts = line.split(" ");
+                #{
+                    text: parts[1],
+                    user: parts[0],
+                    level: record.level,
+                    port: record.fields.port + 1,
+                    tags: [1, 2.5, true, ()],
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut record = Record::new("alice logged in");
+        record.level = Some(Level::Warn);
+        record.fields.insert("port", 21);
+
+        let output = script.process(record);
+        assert_eq!(output[0].text, "logged");
+        assert_eq!(output[0].fields.get_str("user"), Some("alice"));
+        assert_eq!(output[0].fields.get_str("level"), Some("WARN"));
+        assert_eq!(output[0].fields.get("port"), Some(&FieldValue::Int(22)));
+        assert_eq!(
+            output[0].fields.get("tags").unwrap().to_string(),
+            "[1, 2.5, true, null]"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            ScriptProcessor::from_source("fn other(line) { line }"),
+            Err(Error::Script { .. })
+        ));
+        assert!(ScriptProcessor::from_source("fn process(line) {").is_err());
+
+        let script = ScriptProcessor::from_source(
+            "fn process(line) { if line == \"x\" { throw \"bad\"; } line }",
+        )
+        .unwrap();
+        // Records the script fails on pass through unchanged
+        assert_eq!(texts(&script.process(Record::new("x"))), vec!["x"]);
+        assert_eq!(script.errors(), 1);
+        assert!(script.last_error().unwrap().contains("bad"));
+
+        let looping = ScriptProcessor::from_source("fn process(line) { loop {} }").unwrap();
+        assert_eq!(texts(&looping.process(Record::new("y"))), vec!["y"]);
+        assert_eq!(looping.errors(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transform.rhai");
+        std::fs::write(&path, "fn process(line) { \"v1\" }").unwrap();
+
+        let script = ScriptProcessor::from_file(&path).unwrap();
+        assert_eq!(texts(&script.process(Record::new("x"))), vec!["v1"]);
+
+        let wait_for = |expected: &'static str| {
+            let script = script.clone();
+            async move {
+                for _ in 0..100 {
+                    if script.process(Record::new("x"))[0].text == expected {
+                        return true;
+                    }
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                false
+            }
+        };
+
+        std::fs::write(&path, "fn process(line) { \"v2\" }").unwrap();
+        assert!(wait_for("v2").await);
+
+        // A broken edit keeps the last good script
+        std::fs::write(&path, "fn process(line) {").unwrap();
+        for _ in 0..100 {
+            if script.last_error().is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(script.last_error().is_some());
+        assert_eq!(texts(&script.process(Record::new("x"))), vec!["v2"]);
+
+        std::fs::write(&path, "fn process(line) { \"v3\" }").unwrap();
+        assert!(wait_for("v3").await);
+    }
+
+    #[test]
+    fn test_from_file_needs_runtime() {
+        assert!(matches!(
+            ScriptProcessor::from_file("missing.rhai"),
+            Err(Error::Script { .. })
+        ));
+    }
+}
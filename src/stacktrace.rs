@@ -0,0 +1,407 @@
+//! Built-in detectors for common exception and panic traces.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// A language whose stack traces can be recognised and kept with the line that started them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StackTraceDetector {
+    /// Java and JVM exceptions: `at ...` frames, `Caused by:`, `... N more`.
+    Java,
+    /// Python tracebacks starting with `Traceback (most recent call last):` and
+    /// ending at the `Name: message` exception line.
+    Python,
+    /// Go panics with `goroutine N [running]:` dumps.
+    Go,
+    /// Rust panics starting with `thread '...' panicked at`.
+    Rust,
+}
+
+impl StackTraceDetector {
+    /// All available detectors.
+    pub const ALL: [StackTraceDetector; 4] = [
+        StackTraceDetector::Java,
+        StackTraceDetector::Python,
+        StackTraceDetector::Go,
+        StackTraceDetector::Rust,
+    ];
+}
+
+static JAVA_FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s+(at\s|\.\.\. \d+ (more|common frames omitted))").unwrap());
+static JAVA_CAUSE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(Caused by|Suppressed): ").unwrap());
+static JAVA_EXCEPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([a-zA-Z_$][\w$]*\.)+[\w$]*(Exception|Error|Throwable)(: |$)").unwrap()
+});
+static PYTHON_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Traceback \(most recent call last\):").unwrap());
+static PYTHON_CHAIN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(During handling of the above exception|The above exception was the direct cause)",
+    )
+    .unwrap()
+});
+/// `Name: message`, or a bare name that looks like an exception class.
+static PYTHON_EXCEPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^([A-Za-z_][\w.]*: |[A-Za-z_][\w.]*(Error|Exception|Warning|Exit|Interrupt|Iteration)$)",
+    )
+    .unwrap()
+});
+static GO_PANIC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(panic: |fatal error: )").unwrap());
+static GO_GOROUTINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^goroutine \d+ \[[^\]]*\]:$").unwrap());
+static GO_FRAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\t|created by |\[signal |exit status \d+|[\w./*()\[\]-]+\(.*\)$)").unwrap()
+});
+static RUST_PANIC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^thread '[^']*' panicked at ").unwrap());
+static RUST_FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s+\d+: |\s+at |note: |stack backtrace:)").unwrap());
+
+/// The trace currently being followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TraceState {
+    None,
+    Java,
+    Python,
+    /// A Python traceback that ended, but may be followed by a chained one.
+    PythonEnded,
+    Go,
+    /// A Rust panic, with whether the next line is the panic message.
+    Rust {
+        expect_message: bool,
+    },
+}
+
+/// Tracks whether lines belong to a stack trace using the selected detectors.
+#[derive(Debug, Clone)]
+pub(crate) struct StackTraceMatcher {
+    detectors: Vec<StackTraceDetector>,
+    state: TraceState,
+}
+
+impl StackTraceMatcher {
+    pub(crate) fn new(detectors: Vec<StackTraceDetector>) -> Self {
+        Self {
+            detectors,
+            state: TraceState::None,
+        }
+    }
+
+    /// Returns true while a trace is being followed.
+    ///
+    /// Blank lines separate chained exceptions and the parts of a Go panic, so
+    /// inside a trace they belong to it only if the line after them continues it.
+    pub(crate) fn in_trace(&self) -> bool {
+        self.state != TraceState::None
+    }
+
+    /// Returns true if the line continues the current record rather than starting a new one.
+    pub(crate) fn continues(&mut self, line: &str) -> bool {
+        if self.continues_current(line) {
+            return true;
+        }
+
+        self.state = TraceState::None;
+        self.detect_start(line)
+    }
+
+    /// Check the line against the trace that is currently being followed
+    fn continues_current(&mut self, line: &str) -> bool {
+        match self.state {
+            TraceState::None => false,
+            TraceState::Java => {
+                JAVA_FRAME.is_match(line)
+                    || JAVA_CAUSE.is_match(line)
+                    || JAVA_EXCEPTION.is_match(line)
+            }
+            TraceState::Python => {
+                if line.starts_with([' ', '\t'])
+                    || PYTHON_CHAIN.is_match(line)
+                    || PYTHON_START.is_match(line)
+                {
+                    true
+                } else if PYTHON_EXCEPTION.is_match(line) {
+                    // The exception line ends the traceback, unless another is chained to it
+                    self.state = TraceState::PythonEnded;
+                    true
+                } else {
+                    false
+                }
+            }
+            TraceState::PythonEnded => {
+                if PYTHON_CHAIN.is_match(line) || PYTHON_START.is_match(line) {
+                    self.state = TraceState::Python;
+                    true
+                } else {
+                    false
+                }
+            }
+            TraceState::Go => GO_GOROUTINE.is_match(line) || GO_FRAME.is_match(line),
+            TraceState::Rust { expect_message } => {
+                self.state = TraceState::Rust {
+                    expect_message: false,
+                };
+                expect_message || RUST_FRAME.is_match(line) || line.starts_with([' ', '\t'])
+            }
+        }
+    }
+
+    /// Check whether the line begins a trace, returning true if it attaches to the previous line
+    fn detect_start(&mut self, line: &str) -> bool {
+        for detector in &self.detectors {
+            match detector {
+                StackTraceDetector::Java => {
+                    if JAVA_FRAME.is_match(line)
+                        || JAVA_CAUSE.is_match(line)
+                        || JAVA_EXCEPTION.is_match(line)
+                    {
+                        self.state = TraceState::Java;
+                        return true;
+                    }
+                }
+                StackTraceDetector::Python => {
+                    if PYTHON_START.is_match(line) || PYTHON_CHAIN.is_match(line) {
+                        self.state = TraceState::Python;
+                        return true;
+                    }
+                }
+                StackTraceDetector::Go => {
+                    if GO_GOROUTINE.is_match(line) {
+                        self.state = TraceState::Go;
+                        return true;
+                    }
+                    if GO_PANIC.is_match(line) {
+                        // A panic is the start of its own record
+                        self.state = TraceState::Go;
+                        return false;
+                    }
+                }
+                StackTraceDetector::Rust => {
+                    if RUST_PANIC.is_match(line) {
+                        // A panic is the start of its own record
+                        self.state = TraceState::Rust {
+                            expect_message: line.trim_end().ends_with(':'),
+                        };
+                        return false;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Group lines into records using the matcher
+    fn group(detectors: &[StackTraceDetector], lines: &[&str]) -> Vec<String> {
+        let mut matcher = StackTraceMatcher::new(detectors.to_vec());
+        let mut records: Vec<String> = Vec::new();
+        let mut blank_lines = 0;
+        for line in lines {
+            if matcher.in_trace() && line.is_empty() {
+                blank_lines += 1;
+                continue;
+            }
+            let continues = matcher.continues(line);
+            match records.last_mut() {
+                Some(last) if continues => {
+                    last.push_str(&"\n".repeat(blank_lines + 1));
+                    last.push_str(line);
+                }
+                _ => {
+                    records.extend(std::iter::repeat_n(String::new(), blank_lines));
+                    records.push(line.to_string());
+                }
+            }
+            blank_lines = 0;
+        }
+        records.extend(std::iter::repeat_n(String::new(), blank_lines));
+        records
+    }
+
+    #[test]
+    fn test_java_trace() {
+        let records = group(
+            &[StackTraceDetector::Java],
+            &[
+                "2024-01-01 ERROR Request failed",
+                "java.lang.IllegalStateException: boom",
+                "\tat com.example.Service.run(Service.java:10)",
+                "Caused by: java.io.IOException: disk",
+                "\tat com.example.Disk.read(Disk.java:3)",
+                "\t... 5 more",
+                "2024-01-01 INFO Next",
+            ],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].starts_with("2024-01-01 ERROR Request failed\njava.lang"));
+        assert!(records[0].ends_with("\t... 5 more"));
+        assert_eq!(records[1], "2024-01-01 INFO Next");
+    }
+
+    #[test]
+    fn test_python_traceback() {
+        let records = group(
+            &[StackTraceDetector::Python],
+            &[
+                "ERROR:root:failed",
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 3, in <module>",
+                "    main()",
+                "ValueError: bad value",
+                "",
+                "During handling of the above exception, another exception occurred:",
+                "",
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 5, in <module>",
+                "KeyError: 'x'",
+                "INFO:root:done",
+            ],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].ends_with("KeyError: 'x'"));
+        assert_eq!(records[1], "INFO:root:done");
+    }
+
+    #[test]
+    fn test_python_exception_line_shape() {
+        let records = group(
+            &[StackTraceDetector::Python],
+            &[
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 3, in <module>",
+                "KeyboardInterrupt",
+                "INFO:root:restarted",
+                "Traceback (most recent call last):",
+                "  File \"app.py\", line 8, in <module>",
+                "shutting_down",
+                "exiting",
+            ],
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\nKeyboardInterrupt",
+                "INFO:root:restarted\nTraceback (most recent call last):\n  File \"app.py\", line 8, in <module>",
+                "shutting_down",
+                "exiting",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_line_after_trace_ends_it() {
+        let records = group(
+            &[StackTraceDetector::Java],
+            &[
+                "ERROR failed",
+                "java.lang.IllegalStateException: boom",
+                "\tat com.example.Service.run(Service.java:10)",
+                "",
+                "INFO next",
+                "",
+            ],
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "ERROR failed\njava.lang.IllegalStateException: boom\n\tat com.example.Service.run(Service.java:10)",
+                "",
+                "INFO next",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_panic() {
+        let records = group(
+            &[StackTraceDetector::Go],
+            &[
+                "starting server",
+                "panic: runtime error: index out of range [5] with length 3",
+                "",
+                "goroutine 1 [running]:",
+                "main.main()",
+                "\t/tmp/main.go:8 +0x1d",
+                "exit status 2",
+                "next line",
+            ],
+        );
+
+        assert_eq!(
+            records,
+            vec![
+                "starting server",
+                "panic: runtime error: index out of range [5] with length 3\n\ngoroutine 1 [running]:\nmain.main()\n\t/tmp/main.go:8 +0x1d\nexit status 2",
+                "next line",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rust_panic() {
+        let records = group(
+            &[StackTraceDetector::Rust],
+            &[
+                "INFO starting",
+                "thread 'main' panicked at src/main.rs:2:5:",
+                "explicit panic",
+                "stack backtrace:",
+                "   0: rust_begin_unwind",
+                "             at /rustc/library/std/src/panicking.rs:597:5",
+                "note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.",
+                "INFO after",
+            ],
+        );
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], "INFO starting");
+        assert!(
+            records[1].starts_with("thread 'main' panicked at src/main.rs:2:5:\nexplicit panic")
+        );
+        assert!(records[1].ends_with("verbose backtrace."));
+        assert_eq!(records[2], "INFO after");
+    }
+
+    #[test]
+    fn test_rust_panic_inline_message() {
+        let records = group(
+            &[StackTraceDetector::Rust],
+            &[
+                "thread 'worker' panicked at 'boom', src/lib.rs:1:1",
+                "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace",
+                "plain line",
+            ],
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], "plain line");
+    }
+
+    #[test]
+    fn test_unselected_detectors_are_ignored() {
+        let records = group(
+            &[StackTraceDetector::Java],
+            &["ERROR failed", "Traceback (most recent call last):"],
+        );
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn test_plain_lines_are_separate() {
+        let records = group(&StackTraceDetector::ALL, &["one", "two", "three"]);
+        assert_eq!(records, vec!["one", "two", "three"]);
+    }
+}
@@ -0,0 +1,45 @@
+//! Statistics collected by the background reader task.
+
+use std::collections::BTreeMap;
+
+/// Counters describing the work a stream's reader task has done.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Total number of bytes read from the file.
+    pub bytes_read: u64,
+    /// Total number of lines sent through the stream.
+    pub lines_emitted: u64,
+    /// Total number of batches sent through the stream.
+    pub batches_emitted: u64,
+    /// Number of records dropped by the minimum level or the record filter.
+    pub records_filtered: u64,
+    /// Number of repeated records collapsed into a "last message repeated" record.
+    pub records_deduplicated: u64,
+    /// Number of records dropped by sampling or rate limiting.
+    pub records_sampled: u64,
+    /// Number of values redacted by each redaction rule, keyed by rule name.
+    pub redactions: BTreeMap<String, u64>,
+    /// Number of times the file was detected as truncated.
+    pub truncations: u64,
+    /// Read position in the file when the statistics were taken.
+    pub position: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_stats_default() {
+        let stats = StreamStats::default();
+        assert_eq!(stats.bytes_read, 0);
+        assert_eq!(stats.lines_emitted, 0);
+        assert_eq!(stats.batches_emitted, 0);
+        assert_eq!(stats.records_filtered, 0);
+        assert_eq!(stats.records_deduplicated, 0);
+        assert_eq!(stats.records_sampled, 0);
+        assert!(stats.redactions.is_empty());
+        assert_eq!(stats.truncations, 0);
+        assert_eq!(stats.position, 0);
+    }
+}
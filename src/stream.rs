@@ -1,51 +1,164 @@
 //! Stream implementation for reading log files with real-time monitoring.
 
+use crate::config::StreamConfig;
+use crate::control::StreamControl;
 use crate::error::{Error, Result};
-use crate::reader::read_file_content;
+use crate::reader::{
+    ReaderState, RecordSender, flush_expired_records, flush_pending_records, read_file_content,
+};
+use crate::record::Record;
+use crate::stats::StreamStats;
 use crate::watcher::{FileWatcher, is_event_relevant_to_file};
 use futures::Stream;
+use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 
 /// A stream that monitors a file for changes and yields new content.
-pub struct LogStream {
-    receiver: mpsc::UnboundedReceiver<Result<Vec<String>>>,
-    _shutdown_tx: broadcast::Sender<()>,
-    _task_handle: JoinHandle<()>,
+///
+/// By default each item is a batch of lines as `Vec<String>`. Use
+/// [`into_records`](LogStream::into_records) to receive [`Record`]s instead,
+/// which carry the fields extracted by the configured parser.
+pub struct LogStream<T = String> {
+    receiver: mpsc::UnboundedReceiver<Result<Vec<Record>>>,
+    shutdown: ShutdownSignal,
+    control: StreamControl,
+    task_handle: Option<JoinHandle<Result<StreamStats>>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// The final state of a stream returned by [`LogStream::close`].
+#[derive(Debug, Default)]
+pub struct CloseSummary<T = String> {
+    /// Statistics for the whole lifetime of the stream.
+    pub stats: StreamStats,
+    /// Lines that were read but not yet consumed from the stream.
+    pub pending: Vec<T>,
+    /// Errors that were sent but not yet consumed from the stream, followed by
+    /// the error the reader task failed with, if any. The statistics are empty
+    /// when the task failed.
+    pub errors: Vec<Error>,
+}
+
+/// Signals the background task to shut down, including when dropped.
+struct ShutdownSignal(broadcast::Sender<()>);
+
+impl ShutdownSignal {
+    fn send(&self) {
+        // Ignore errors if already dropped or no receivers
+        let _ = self.0.send(());
+    }
+}
+
+impl Drop for ShutdownSignal {
+    fn drop(&mut self) {
+        // Send shutdown signal when the stream is dropped. The task handle will be
+        // detached when it's dropped, but the signal lets the task stop cleanly.
+        self.send();
+    }
 }
 
 impl LogStream {
     /// Creates a new LogStream for the specified file.
     pub async fn new<P: AsRef<Path>>(path: P, separator: Option<String>) -> Result<Self> {
+        let config = match separator {
+            Some(separator) => StreamConfig::new().separator(separator),
+            None => StreamConfig::default(),
+        };
+        Self::with_config(path, config).await
+    }
+
+    /// Creates a new LogStream for the specified file using the given configuration.
+    pub async fn with_config<P: AsRef<Path>>(path: P, config: StreamConfig) -> Result<Self> {
+        config.validate()?;
         let file_path = path.as_ref().to_path_buf();
-        let separator = separator.unwrap_or_else(|| "\n".to_string());
 
         let (tx, rx) = mpsc::unbounded_channel();
         let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let (control, pause_rx) = StreamControl::new();
 
         // Spawn background task to handle file watching and reading
         let task_file_path = file_path.clone();
-        let task_separator = separator.clone();
         let task_tx = tx.clone();
 
         let task_handle = tokio::spawn(async move {
-            if let Err(e) =
-                file_reader_task(task_file_path, task_separator, task_tx, shutdown_rx).await
-            {
-                // Log error or send it through channel
+            let result =
+                file_reader_task(task_file_path, config, task_tx, shutdown_rx, pause_rx).await;
+            if let Err(e) = &result {
+                // Log error, it is also returned to callers of `close`
                 eprintln!("File reader task error: {}", e);
             }
+            result
         });
 
         Ok(LogStream {
             receiver: rx,
-            _shutdown_tx: shutdown_tx,
-            _task_handle: task_handle,
+            shutdown: ShutdownSignal(shutdown_tx),
+            control,
+            task_handle: Some(task_handle),
+            _marker: PhantomData,
         })
     }
+}
+
+impl<T: From<Record>> LogStream<T> {
+    /// Converts the stream to yield full [`Record`]s instead of lines.
+    pub fn into_records(self) -> LogStream<Record> {
+        LogStream {
+            receiver: self.receiver,
+            shutdown: self.shutdown,
+            control: self.control,
+            task_handle: self.task_handle,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a handle for pausing and resuming this stream.
+    pub fn control(&self) -> StreamControl {
+        self.control.clone()
+    }
+
+    /// Stops watching the file and waits for the reader task to finish.
+    ///
+    /// The reader task performs a final read before exiting, so content written
+    /// just before closing is not lost. Any lines not yet consumed from the
+    /// stream are returned in the summary along with the final statistics and
+    /// any errors that were still waiting to be consumed, including the error
+    /// the reader task failed with.
+    pub async fn close(mut self) -> CloseSummary<T> {
+        self.shutdown.send();
+
+        let result = match self.task_handle.take() {
+            Some(handle) => handle.await.unwrap_or_else(|e| {
+                Err(Error::TaskFailed {
+                    message: e.to_string(),
+                })
+            }),
+            None => Ok(StreamStats::default()),
+        };
+
+        // The task has finished, so everything it sent is already queued
+        let mut summary = CloseSummary {
+            stats: StreamStats::default(),
+            pending: Vec::new(),
+            errors: Vec::new(),
+        };
+        while let Ok(item) = self.receiver.try_recv() {
+            match item {
+                Ok(records) => summary.pending.extend(records.into_iter().map(T::from)),
+                Err(e) => summary.errors.push(e),
+            }
+        }
+        match result {
+            Ok(stats) => summary.stats = stats,
+            Err(e) => summary.errors.push(e),
+        }
+        summary
+    }
 
     /// Check if the stream has been closed/dropped
     #[cfg(test)]
@@ -54,31 +167,29 @@ impl LogStream {
     }
 }
 
-impl Drop for LogStream {
-    fn drop(&mut self) {
-        // Send shutdown signal - ignore errors if already dropped or no receivers
-        let _ = self._shutdown_tx.send(());
-
-        // The task handle will be automatically aborted when it's dropped,
-        // but we've also sent a graceful shutdown signal for clean cleanup
-    }
-}
-
 /// Background task that handles file watching and reading
 async fn file_reader_task(
     file_path: PathBuf,
-    separator: String,
-    tx: mpsc::UnboundedSender<Result<Vec<String>>>,
+    config: StreamConfig,
+    tx: RecordSender,
     mut shutdown_rx: broadcast::Receiver<()>,
-) -> Result<()> {
-    let mut last_position = 0u64;
+    mut pause_rx: watch::Receiver<bool>,
+) -> Result<StreamStats> {
+    let mut state = ReaderState::new(&config);
+
+    // Skip existing content when only appended lines are wanted
+    if config.start_at_end
+        && let Ok(metadata) = tokio::fs::metadata(&file_path).await
+    {
+        state.position = metadata.len();
+    }
 
     // Read existing content in the file.
-    if file_path.exists() {
-        if let Err(e) = read_file_content(&file_path, &mut last_position, &separator, &tx).await {
-            let _ = tx.send(Err(e));
-            return Ok(());
-        }
+    if file_path.exists()
+        && let Err(e) = read_file_content(&file_path, &mut state, &config, &tx).await
+    {
+        let _ = tx.send(Err(e));
+        return Ok(state.into_stats());
     }
 
     // Now start watching for future changes
@@ -93,23 +204,50 @@ async fn file_reader_task(
 
     // Watch for file changes
     loop {
+        let flush_deadline = state.flush_deadline();
+
         tokio::select! {
             // Check for shutdown signal
             _ = shutdown_rx.recv() => {
-                // Graceful shutdown requested
+                // Graceful shutdown requested, pick up anything written since the last event
+                if let Err(e) = read_file_content(&file_path, &mut state, &config, &tx).await {
+                    let _ = tx.send(Err(e));
+                }
+                flush_pending_records(&mut state, &config, &tx).await;
                 break;
             }
 
+            // Emit held back records once nothing more has arrived for them
+            _ = sleep_until_deadline(flush_deadline), if flush_deadline.is_some() && !*pause_rx.borrow() => {
+                flush_expired_records(&mut state, &config, &tx).await;
+            }
+
+            // Catch up from the last position when resumed
+            changed = pause_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let paused = *pause_rx.borrow_and_update();
+                if !paused
+                    && let Err(e) = read_file_content(&file_path, &mut state, &config, &tx).await
+                {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            }
+
             // Process file events
             event = watcher.next_event() => {
                 match event {
                     Some(Ok(event)) => {
-                        // Filter events to only include those affecting our target file
-                        if is_event_relevant_to_file(&event, &file_name) {
-                            if let Err(e) = read_file_content(&file_path, &mut last_position, &separator, &tx).await {
-                                let _ = tx.send(Err(e));
-                                break;
-                            }
+                        // Filter events to only include those affecting our target file,
+                        // while paused they are dropped and picked up again on resume
+                        if is_event_relevant_to_file(&event, &file_name)
+                            && !*pause_rx.borrow()
+                            && let Err(e) = read_file_content(&file_path, &mut state, &config, &tx).await
+                        {
+                            let _ = tx.send(Err(e));
+                            break;
                         }
                     }
                     Some(Err(e)) => {
@@ -125,20 +263,35 @@ async fn file_reader_task(
         }
     }
 
-    Ok(())
+    Ok(state.into_stats())
+}
+
+/// Sleep until the deadline, or forever if there is none
+async fn sleep_until_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
 }
 
-impl Stream for LogStream {
-    type Item = Result<Vec<String>>;
+impl<T: From<Record>> Stream for LogStream<T> {
+    type Item = Result<Vec<T>>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.receiver).poll_recv(cx)
+        Pin::new(&mut self.receiver).poll_recv(cx).map(|item| {
+            item.map(|batch| batch.map(|records| records.into_iter().map(T::from).collect()))
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::EmptyRecordPolicy;
+    use crate::delimited::DelimitedFormat;
+    use crate::multiline::MultilineConfig;
+    use crate::parser::LogfmtParser;
+    use crate::test_helpers::TempLogFile;
     use std::time::Duration;
     use tokio_stream::StreamExt;
 
@@ -221,7 +374,7 @@ mod tests {
 
         assert!(!items.is_empty());
         // Now we get Vec<String> items, so check the first Vec contains the expected content
-        assert!(items[0].len() > 0);
+        assert!(!items[0].is_empty());
         assert!(items[0][0].contains("Starting application"));
     }
 
@@ -240,6 +393,100 @@ mod tests {
         assert!(lines.len() > 1);
     }
 
+    #[tokio::test]
+    async fn test_log_stream_with_config_keeps_blank_lines() {
+        let temp_file = TempLogFile::with_content("record 1\r\n\r\nrecord 2").unwrap();
+        let config = StreamConfig::new()
+            .empty_records(EmptyRecordPolicy::KeepAll)
+            .trim_carriage_return(true);
+        let mut stream = LogStream::with_config(temp_file.path(), config)
+            .await
+            .unwrap();
+
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert_eq!(items, vec![vec!["record 1", "", "record 2"]]);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_close_flushes_partial_record() {
+        let temp_file = TempLogFile::new().unwrap();
+        std::fs::write(temp_file.path(), "complete\nincomplete").unwrap();
+
+        let config = StreamConfig::new().buffer_partial_records(true);
+        let mut stream = LogStream::with_config(temp_file.path(), config)
+            .await
+            .unwrap();
+
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert_eq!(items, vec![vec!["complete"]]);
+
+        let summary = stream.close().await;
+        assert_eq!(summary.pending, vec!["incomplete"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_start_at_end_with_csv_header() {
+        let temp_file = TempLogFile::with_content("id,msg\n1,old\n").unwrap();
+        let config = StreamConfig::new()
+            .start_at_end(true)
+            .delimited(DelimitedFormat::csv());
+        let mut stream = LogStream::with_config(temp_file.path(), config)
+            .await
+            .unwrap()
+            .into_records();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        temp_file.append_content("2,new").unwrap();
+
+        let records = tokio::time::timeout(Duration::from_millis(500), stream.next())
+            .await
+            .expect("Appended row should be emitted")
+            .unwrap()
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields.get_str("msg"), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_multiline_flush_timeout() {
+        let temp_file = TempLogFile::with_content("panic: boom\n    at main.rs:1").unwrap();
+        let multiline =
+            MultilineConfig::continuation_whitespace().flush_timeout(Duration::from_millis(20));
+        let config = StreamConfig::new().multiline(multiline);
+        let mut stream = LogStream::with_config(temp_file.path(), config)
+            .await
+            .unwrap();
+
+        // The record is held until the flush timeout passes
+        let item = tokio::time::timeout(Duration::from_millis(200), stream.next())
+            .await
+            .expect("Record should be flushed after the timeout")
+            .unwrap()
+            .unwrap();
+        assert_eq!(item, vec!["panic: boom\n    at main.rs:1"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_into_records_with_parser() {
+        let temp_file = TempLogFile::with_content("level=warn msg=\"disk low\" pct=91").unwrap();
+        let config = StreamConfig::new().parser(LogfmtParser::new());
+        let mut stream = LogStream::with_config(temp_file.path(), config)
+            .await
+            .unwrap()
+            .into_records();
+
+        let records = tokio::time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields.get_str("level"), Some("warn"));
+        assert_eq!(records[0].fields.get_str("msg"), Some("disk low"));
+        assert_eq!(records[0].fields.get_str("pct"), Some("91"));
+    }
+
     #[tokio::test]
     async fn test_log_stream_empty_file() {
         let mut stream = LogStream::new("fixtures/empty.log", None).await.unwrap();
@@ -249,18 +496,129 @@ mod tests {
         assert!(items.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_log_stream_close_returns_pending_lines() {
+        let temp_file = TempLogFile::with_content("line 1").unwrap();
+        let stream = LogStream::new(temp_file.path(), None).await.unwrap();
+
+        // Let the initial read happen, then write more without consuming anything
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        temp_file.append_content("line 2").unwrap();
+
+        let summary = stream.close().await;
+
+        assert_eq!(summary.pending, vec!["line 1", "line 2"]);
+        assert_eq!(summary.stats.lines_emitted, 2);
+        assert_eq!(summary.stats.bytes_read, 14);
+        assert_eq!(summary.stats.position, 14);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_rejects_empty_separator() {
+        let result = LogStream::new("fixtures/simple_append.log", Some(String::new())).await;
+        assert!(matches!(result, Err(Error::InvalidFormat { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_close_returns_errors() {
+        // Reading a directory fails, the error waits in the channel
+        let stream = LogStream::new("fixtures", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let summary = stream.close().await;
+
+        assert!(summary.pending.is_empty());
+        assert_eq!(summary.errors.len(), 1);
+        assert!(matches!(summary.errors[0], Error::Io(_)));
+        assert_eq!(summary.stats, StreamStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_close_keeps_lines_when_final_read_fails() {
+        let temp_file = TempLogFile::with_content("line 1\n").unwrap();
+        let stream = LogStream::new(temp_file.path(), None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Replace the file with a directory so the final read fails
+        std::fs::remove_file(temp_file.path()).unwrap();
+        std::fs::create_dir(temp_file.path()).unwrap();
+
+        let summary = stream.close().await;
+
+        assert_eq!(summary.pending, vec!["line 1"]);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(matches!(summary.errors[0], Error::Io(_)));
+        assert_eq!(summary.stats.lines_emitted, 1);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_close_returns_task_error() {
+        let stream = LogStream::new("missing_directory/app.log", None)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let summary = stream.close().await;
+
+        assert_eq!(summary.errors.len(), 1);
+        assert!(matches!(summary.errors[0], Error::Watcher(_)));
+        assert_eq!(summary.stats, StreamStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_close_after_consuming() {
+        let mut stream = LogStream::new("fixtures/simple_append.log", None)
+            .await
+            .unwrap();
+
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert_eq!(items.len(), 1);
+
+        let summary = stream.close().await;
+        assert!(summary.pending.is_empty());
+        assert_eq!(summary.stats.lines_emitted, items[0].len() as u64);
+        assert_eq!(summary.stats.batches_emitted, 1);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_pause_and_resume() {
+        let temp_file = TempLogFile::with_content("line 1").unwrap();
+        let mut stream = LogStream::new(temp_file.path(), None).await.unwrap();
+
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert_eq!(items, vec![vec!["line 1".to_string()]]);
+
+        let control = stream.control();
+        control.pause();
+        assert!(control.is_paused());
+
+        // Writes while paused are not delivered
+        temp_file.append_content("line 2").unwrap();
+        temp_file.append_content("line 3").unwrap();
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert!(items.is_empty());
+
+        // Resuming catches up from the saved position
+        control.resume();
+        let items = collect_stream_items(&mut stream, 1, Duration::from_millis(100)).await;
+        assert_eq!(
+            items,
+            vec![vec!["line 2".to_string(), "line 3".to_string()]]
+        );
+    }
+
     #[tokio::test]
     async fn test_file_reader_task_shutdown_signal() {
         let file_path = PathBuf::from("fixtures/simple_append.log");
-        let separator = "\n".to_string();
+        let config = StreamConfig::default();
         let (tx, mut rx) = mpsc::unbounded_channel();
         let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let (_control, pause_rx) = StreamControl::new();
 
         // Start the task
-        let task_handle =
-            tokio::spawn(
-                async move { file_reader_task(file_path, separator, tx, shutdown_rx).await },
-            );
+        let task_handle = tokio::spawn(async move {
+            file_reader_task(file_path, config, tx, shutdown_rx, pause_rx).await
+        });
 
         // Let it run briefly
         tokio::time::sleep(Duration::from_millis(10)).await;
@@ -284,12 +642,13 @@ mod tests {
     #[tokio::test]
     async fn test_file_reader_task_error_handling() {
         let file_path = PathBuf::from("/invalid/path/that/does/not/exist.log");
-        let separator = "\n".to_string();
+        let config = StreamConfig::default();
         let (tx, mut rx) = mpsc::unbounded_channel();
         let (_shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let (_control, pause_rx) = StreamControl::new();
 
         // Task should handle invalid paths gracefully
-        let result = file_reader_task(file_path, separator, tx, shutdown_rx).await;
+        let result = file_reader_task(file_path, config, tx, shutdown_rx, pause_rx).await;
 
         // Task should complete without panicking
         assert!(result.is_ok() || result.is_err());
@@ -0,0 +1,474 @@
+//! Parsing of the timestamp formats commonly found in logs, and extraction of
+//! the event time of a record.
+
+use crate::record::{FieldValue, Fields, Record};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Formats that carry their own offset.
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%d/%b/%Y:%H:%M:%S%.f %z",
+];
+
+/// Formats without an offset, read in the default timezone.
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%d/%b/%Y:%H:%M:%S%.f",
+    "%b %d %Y %H:%M:%S%.f",
+];
+
+/// Formats without a year, such as syslog's `Oct 17 12:00:00`.
+const YEARLESS_FORMATS: &[&str] = &["%b %d %H:%M:%S%.f"];
+
+/// Specifiers that put a year into a format.
+const YEAR_SPECIFIERS: &[&str] = &[
+    "%Y", "%y", "%G", "%g", "%C", "%D", "%F", "%c", "%x", "%+", "%s",
+];
+
+/// Specifiers that put an offset into a format.
+const OFFSET_SPECIFIERS: &[&str] = &["%z", "%:z", "%::z", "%#z", "%+", "%s"];
+
+/// How far in the future a yearless timestamp may be before it is taken to be from last year.
+const YEAR_INFERENCE_SLACK: Duration = Duration::days(1);
+
+/// Timestamps found anywhere in the text of a record, leftmost first.
+static TEXT_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        (?P<iso>\b\d{4}[-/]\d{2}[-/]\d{2}[T\ ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:\ ?Z|\ ?[+-]\d{2}:?\d{2})?)
+        | \[(?P<apache>\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2}(?:\ [+-]\d{4})?)\]
+        | (?P<syslog>\b(?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)\ +\d{1,2}(?:\ \d{4})?\ \d{2}:\d{2}:\d{2}(?:\.\d+)?)
+        | ^(?P<epoch>\d{13}|\d{10}(?:\.\d+)?)\b
+        ",
+    )
+    .unwrap()
+});
+
+/// Settings for finding the event time of each record.
+///
+/// The timestamp is looked up in this order:
+///
+/// 1. the first of the [`fields`](Self::fields) set by the parser,
+/// 2. the start of the text, using the explicit [`format`](Self::format)s,
+/// 3. the first timestamp in the text in a recognised format: RFC 3339 and ISO
+///    8601 with or without a zone, syslog's `Oct 17 12:00:00`, the access log
+///    `[17/Oct/2026:12:00:00 +0000]` and epoch seconds or milliseconds at the
+///    start of the line,
+/// 4. the same fields in the record metadata, such as a container runtime `time`.
+///
+/// Times without a zone are read in the [`timezone`](Self::timezone), UTC by
+/// default. Times without a year are given the most recent year that does not
+/// put them in the future.
+///
+/// # Example
+///
+/// ```rust
+/// use log_reader::{StreamConfig, TimestampConfig, Tz};
+///
+/// let timestamps = TimestampConfig::new()
+///     .format("%d.%m.%Y %H:%M:%S")
+///     .timezone(Tz::Europe__Berlin);
+/// let config = StreamConfig::new().timestamps(timestamps);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampConfig {
+    pub(crate) fields: Vec<String>,
+    pub(crate) formats: Vec<String>,
+    pub(crate) timezone: Tz,
+}
+
+impl TimestampConfig {
+    /// Fields checked for the event time by default.
+    pub const DEFAULT_FIELDS: &[&str] = &["timestamp", "@timestamp", "time", "ts", "datetime"];
+
+    /// Creates a configuration that checks the default fields, recognises the
+    /// common formats and reads times without a zone as UTC.
+    pub fn new() -> Self {
+        Self {
+            fields: Self::DEFAULT_FIELDS.iter().map(|s| s.to_string()).collect(),
+            formats: Vec::new(),
+            timezone: Tz::UTC,
+        }
+    }
+
+    /// Sets the fields checked for the event time, in order.
+    pub fn fields(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds a [`chrono` format](chrono::format::strftime) tried before the recognised formats.
+    ///
+    /// A format without a year, such as `%b %d %H:%M:%S`, gets its year inferred.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.formats.push(format.into());
+        self
+    }
+
+    /// Sets the timezone of times written without an offset.
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Find the event time of a record, inferring missing years relative to `now`.
+    pub(crate) fn extract(
+        &self,
+        record: &Record,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<FixedOffset>> {
+        if let Some(timestamp) = self.find_in_fields(&record.fields, now) {
+            return Some(timestamp);
+        }
+
+        let text = record.text.trim_start();
+        let explicit = self
+            .formats
+            .iter()
+            .find_map(|format| parse_format(text, format, &self.timezone, now));
+        if explicit.is_some() {
+            return explicit;
+        }
+
+        let detected = TEXT_TIMESTAMP.find_iter(&record.text).find_map(|found| {
+            parse_in(found.as_str().trim_matches(['[', ']']), &self.timezone, now)
+        });
+        if detected.is_some() {
+            return detected;
+        }
+
+        self.find_in_fields(&record.metadata, now)
+    }
+
+    /// Use the first configured field holding something that reads as a time
+    fn find_in_fields(&self, fields: &Fields, now: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+        self.fields.iter().find_map(|name| match fields.get(name)? {
+            FieldValue::Timestamp(timestamp) => Some(*timestamp),
+            FieldValue::String(text) => self
+                .formats
+                .iter()
+                .find_map(|format| parse_format(text, format, &self.timezone, now))
+                .or_else(|| parse_in(text, &self.timezone, now)),
+            value @ (FieldValue::Int(_) | FieldValue::Float(_)) => parse_epoch(&value.to_string()),
+            _ => None,
+        })
+    }
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a timestamp in one of the common log formats.
+///
+/// Understands RFC 3339/ISO 8601 (with `T` or a space), RFC 2822, the access
+/// log `10/Oct/2023:13:55:36 -0700` form, syslog's yearless `Oct 17 12:00:00`
+/// and Unix epoch seconds or milliseconds. Timestamps without an offset are
+/// read as UTC.
+pub(crate) fn parse_timestamp(text: &str) -> Option<DateTime<FixedOffset>> {
+    parse_in(text, &Tz::UTC, Utc::now())
+}
+
+/// Parse a timestamp, reading times without an offset in `timezone`
+fn parse_in(text: &str, timezone: &Tz, now: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+        return Some(timestamp);
+    }
+    if let Ok(timestamp) = DateTime::parse_from_rfc2822(text) {
+        return Some(timestamp);
+    }
+
+    // Log4j style `10:00:00,250` fractions
+    let text = &text.replacen(',', ".", 1);
+    for format in OFFSET_FORMATS {
+        if let Ok(timestamp) = DateTime::parse_from_str(text, format) {
+            return Some(timestamp);
+        }
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(localize(timestamp, timezone));
+        }
+    }
+    for format in YEARLESS_FORMATS {
+        if let Some(timestamp) = infer_year(text, format, timezone, now, false) {
+            return Some(timestamp);
+        }
+    }
+
+    parse_epoch(text)
+}
+
+/// Parse the start of `text` with a user supplied format
+fn parse_format(
+    text: &str,
+    format: &str,
+    timezone: &Tz,
+    now: DateTime<Utc>,
+) -> Option<DateTime<FixedOffset>> {
+    let has = |specifiers: &[&str]| specifiers.iter().any(|s| format.contains(s));
+
+    if !has(YEAR_SPECIFIERS) {
+        return infer_year(text, format, timezone, now, true);
+    }
+    if has(OFFSET_SPECIFIERS) {
+        return DateTime::parse_and_remainder(text, format)
+            .ok()
+            .map(|(timestamp, _)| timestamp);
+    }
+    NaiveDateTime::parse_and_remainder(text, format)
+        .ok()
+        .map(|(timestamp, _)| localize(timestamp, timezone))
+}
+
+/// Parse a time without a year, using the latest year that does not put it in the future
+fn infer_year(
+    text: &str,
+    format: &str,
+    timezone: &Tz,
+    now: DateTime<Utc>,
+    allow_remainder: bool,
+) -> Option<DateTime<FixedOffset>> {
+    let format = format!("%Y {}", format);
+    let current_year = now.with_timezone(timezone).year();
+
+    // Going back more than one year only matters for February 29
+    (0..=4).find_map(|years_back| {
+        let text = format!("{} {}", current_year - years_back, text);
+        let naive = if allow_remainder {
+            NaiveDateTime::parse_and_remainder(&text, &format).ok()?.0
+        } else {
+            NaiveDateTime::parse_from_str(&text, &format).ok()?
+        };
+        let timestamp = localize(naive, timezone);
+        (timestamp <= now + YEAR_INFERENCE_SLACK).then_some(timestamp)
+    })
+}
+
+/// Read a time without an offset in `timezone`
+fn localize(timestamp: NaiveDateTime, timezone: &Tz) -> DateTime<FixedOffset> {
+    match timezone.from_local_datetime(&timestamp).earliest() {
+        Some(timestamp) => timestamp.fixed_offset(),
+        // Skipped by a daylight saving change, so take the offset in effect a
+        // day before, which is the one in effect just before the gap
+        None => {
+            let offset = timezone
+                .offset_from_utc_datetime(&(timestamp - Duration::days(1)))
+                .fix();
+            DateTime::from_naive_utc_and_offset(timestamp - offset, offset)
+        }
+    }
+}
+
+/// Parse Unix epoch seconds (10 digits) or milliseconds (13 digits), with an optional fraction
+fn parse_epoch(text: &str) -> Option<DateTime<FixedOffset>> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let millis = match whole.len() {
+        10 => {
+            let seconds: i64 = whole.parse().ok()?;
+            let fraction: f64 = format!("0.{}", fraction).parse().unwrap_or(0.0);
+            seconds * 1000 + (fraction * 1000.0) as i64
+        }
+        13 if fraction.is_empty() => whole.parse().ok()?,
+        _ => return None,
+    };
+
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|timestamp| timestamp.fixed_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc3339(text: &str) -> Option<String> {
+        parse_timestamp(text).map(|timestamp| timestamp.to_rfc3339())
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 10, 12, 0, 0).unwrap()
+    }
+
+    fn extract(config: &TimestampConfig, record: &Record) -> Option<String> {
+        config
+            .extract(record, now())
+            .map(|timestamp| timestamp.to_rfc3339())
+    }
+
+    #[test]
+    fn test_parse_timestamps_with_offset() {
+        assert_eq!(
+            rfc3339("2024-03-01T10:00:00.123+01:00"),
+            Some("2024-03-01T10:00:00.123+01:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("2024-03-01 10:00:00Z"),
+            Some("2024-03-01T10:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("10/Oct/2023:13:55:36 -0700"),
+            Some("2023-10-10T13:55:36-07:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("Tue, 1 Jul 2003 10:52:37 +0200"),
+            Some("2003-07-01T10:52:37+02:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_naive_timestamps_as_utc() {
+        assert_eq!(
+            rfc3339("2024-03-01 10:00:00,250"),
+            Some("2024-03-01T10:00:00.250+00:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("2024/03/01 10:00:00"),
+            Some("2024-03-01T10:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_epoch() {
+        assert_eq!(
+            rfc3339("1700000000"),
+            Some("2023-11-14T22:13:20+00:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("1700000000.5"),
+            Some("2023-11-14T22:13:20.500+00:00".to_string())
+        );
+        assert_eq!(
+            rfc3339("1700000000123"),
+            Some("2023-11-14T22:13:20.123+00:00".to_string())
+        );
+        assert_eq!(rfc3339("12345"), None);
+        assert_eq!(rfc3339("not a time"), None);
+    }
+
+    #[test]
+    fn test_year_inference() {
+        let parse = |text| parse_in(text, &Tz::UTC, now()).map(|t| t.to_rfc3339());
+
+        assert_eq!(
+            parse("Jan  9 23:59:59"),
+            Some("2026-01-09T23:59:59+00:00".to_string())
+        );
+        // Later in the year than now, so it was written last year
+        assert_eq!(
+            parse("Oct 17 12:00:00"),
+            Some("2025-10-17T12:00:00+00:00".to_string())
+        );
+        // A little clock skew is not taken for last year
+        assert_eq!(
+            parse("Jan 11 00:30:00"),
+            Some("2026-01-11T00:30:00+00:00".to_string())
+        );
+        // The most recent leap year
+        assert_eq!(
+            parse("Feb 29 08:00:00"),
+            Some("2024-02-29T08:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_from_text() {
+        let config = TimestampConfig::new();
+        let extract_text = |text: &str| extract(&config, &Record::new(text));
+
+        assert_eq!(
+            extract_text("2026-10-17T12:00:00.5Z INFO started"),
+            Some("2026-10-17T12:00:00.500+00:00".to_string())
+        );
+        assert_eq!(
+            extract_text(r#"10.0.0.1 - - [17/Oct/2025:12:00:00 +0200] "GET / HTTP/1.1" 200"#),
+            Some("2025-10-17T12:00:00+02:00".to_string())
+        );
+        assert_eq!(
+            extract_text("<34>Oct 17 12:00:00 host sshd[1]: accepted"),
+            Some("2025-10-17T12:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            extract_text("1700000000 job finished"),
+            Some("2023-11-14T22:13:20+00:00".to_string())
+        );
+        // Epoch values are only recognised at the start of the line
+        assert_eq!(extract_text("took 1700000000 ns"), None);
+    }
+
+    #[test]
+    fn test_extract_from_fields_and_metadata() {
+        let config = TimestampConfig::new().fields(["at"]);
+        let mut record = Record::new("no time here");
+        assert_eq!(extract(&config, &record), None);
+
+        record.metadata.insert("at", "2026-01-01T00:00:00Z");
+        assert_eq!(
+            extract(&config, &record),
+            Some("2026-01-01T00:00:00+00:00".to_string())
+        );
+
+        record.fields.insert("at", 1700000000123_i64);
+        assert_eq!(
+            extract(&config, &record),
+            Some("2023-11-14T22:13:20.123+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_explicit_formats_and_timezone() {
+        let config = TimestampConfig::new()
+            .format("%d.%m.%Y %H:%M:%S")
+            .format("%m%d %H:%M:%S")
+            .timezone(Tz::Europe__Berlin);
+
+        assert_eq!(
+            extract(&config, &Record::new("17.10.2025 12:00:00 Vorgang beendet")),
+            Some("2025-10-17T12:00:00+02:00".to_string())
+        );
+        // A glog style prefix without a year, in winter time
+        assert_eq!(
+            extract(&config, &Record::new("1201 08:00:00 worker started")),
+            Some("2025-12-01T08:00:00+01:00".to_string())
+        );
+        // Recognised formats without a zone use the timezone as well
+        assert_eq!(
+            extract(&config, &Record::new("at 2025-07-01 09:30:00 ok")),
+            Some("2025-07-01T09:30:00+02:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_time_skipped_by_daylight_saving() {
+        let parse = |text, timezone| parse_in(text, &timezone, now()).map(|t| t.to_rfc3339());
+
+        // Clocks in Berlin went from 02:00 to 03:00 on 2025-03-30
+        assert_eq!(
+            parse("2025-03-30 02:30:00", Tz::Europe__Berlin),
+            Some("2025-03-30T02:30:00+01:00".to_string())
+        );
+        let timestamp = parse_in("2025-03-30 02:30:00", &Tz::Europe__Berlin, now()).unwrap();
+        assert_eq!(timestamp.to_utc().to_rfc3339(), "2025-03-30T01:30:00+00:00");
+        // And in New York from 02:00 to 03:00 on 2025-03-09
+        assert_eq!(
+            parse("2025-03-09 02:15:00", Tz::America__New_York),
+            Some("2025-03-09T02:15:00-05:00".to_string())
+        );
+    }
+}